@@ -0,0 +1,197 @@
+use std::{collections::HashMap, fs, io::Read, path::Path};
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use ignore::overrides::Override;
+
+use crate::{
+    file::{analyze_content, AnalysisOptions, File, FileType},
+    revision::is_overridden,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveKind {
+    /// The kind of archive at `path` by its extension, `None` for directories and other files.
+    pub fn from_path(path: &str) -> Option<Self> {
+        if Path::new(path).is_dir() {
+            return None;
+        }
+        let name = path.to_lowercase();
+        if name.ends_with(".tar") {
+            Some(ArchiveKind::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else if name.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// The files of an archive, read entry by entry without extracting anything, with the same
+/// overrides and analyses as a directory walk. Paths are the entry names inside the archive.
+pub fn get_archive_files(
+    path: &str,
+    kind: ArchiveKind,
+    overrides: &Override,
+    skip_lockfiles: &bool,
+    analysis: &AnalysisOptions,
+) -> Result<Vec<File>> {
+    let mut files: Vec<File> = Vec::new();
+    read_archive(path, kind, overrides, &mut |name, content| {
+        let file = analyze_content(name, &content, analysis);
+        if !(*skip_lockfiles && file.file_type == FileType::Lockfile) {
+            files.push(file);
+        }
+    })?;
+
+    Ok(files)
+}
+
+/// The content of the files of an archive by their path, for the analyses that need more than
+/// the `File`.
+pub fn get_archive_contents(
+    path: &str,
+    kind: ArchiveKind,
+    overrides: &Override,
+) -> Result<HashMap<String, String>> {
+    let mut contents = HashMap::new();
+    read_archive(path, kind, overrides, &mut |name, content| {
+        contents.insert(name.to_string(), content);
+    })?;
+
+    Ok(contents)
+}
+
+// calls `add_file` with the name and content of every file that is not overridden
+fn read_archive(
+    path: &str,
+    kind: ArchiveKind,
+    overrides: &Override,
+    add_file: &mut impl FnMut(&str, String),
+) -> Result<()> {
+    let archive = fs::File::open(path).with_context(|| format!("could not open {path}"))?;
+    let mut add_entry = |name: &str, entry: &mut dyn Read| -> Result<()> {
+        let name = name.trim_start_matches("./");
+        if name.is_empty() || name.contains(".git/") || is_overridden(overrides, name) {
+            return Ok(());
+        }
+        let mut content = Vec::new();
+        entry
+            .read_to_end(&mut content)
+            .with_context(|| format!("could not read {name} in {path}"))?;
+        // files that are not valid UTF-8 are skipped like in a directory walk
+        if let Ok(content) = String::from_utf8(content) {
+            add_file(name, content);
+        }
+        Ok(())
+    };
+
+    match kind {
+        ArchiveKind::Tar => read_tar(archive, &mut add_entry)?,
+        ArchiveKind::TarGz => read_tar(GzDecoder::new(archive), &mut add_entry)?,
+        ArchiveKind::Zip => {
+            let mut zip =
+                zip::ZipArchive::new(archive).with_context(|| format!("could not read {path}"))?;
+            for index in 0..zip.len() {
+                let mut entry = zip.by_index(index)?;
+                if entry.is_file() {
+                    let name = entry.name()?.to_string();
+                    add_entry(&name, &mut entry)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// calls `add_entry` for every regular file, links and directories are skipped
+fn read_tar(
+    reader: impl Read,
+    add_entry: &mut impl FnMut(&str, &mut dyn Read) -> Result<()>,
+) -> Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive
+        .entries()
+        .context("could not read the tar archive")?
+    {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.to_string_lossy().to_string();
+        add_entry(&name, &mut entry)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use ignore::overrides::OverrideBuilder;
+    use std::io;
+
+    fn tar_gz(entries: &[(&str, &str)]) -> io::Result<Vec<u8>> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        for (name, content) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, content.as_bytes())?;
+        }
+        builder.into_inner()?.finish()
+    }
+
+    #[test]
+    fn test_archive_files() {
+        let dir = TempDir::new("archive");
+        let path = dir.join("release-1.0.tgz");
+        let content = tar_gz(&[
+            ("./release/src/main.rs", "fn main() {\n    run();\n}\n"),
+            ("./release/notes.txt", "skipped\n"),
+            ("./release/package-lock.json", "{}\n"),
+        ])
+        .unwrap();
+        fs::write(&path, content).unwrap();
+        let path = path.to_str().unwrap();
+        assert_eq!(ArchiveKind::from_path(path), Some(ArchiveKind::TarGz));
+        assert_eq!(ArchiveKind::from_path(dir.to_str()), None);
+
+        let mut builder = OverrideBuilder::new(path);
+        builder.add("!*.txt").unwrap();
+        let overrides = builder.build().unwrap();
+        let files = get_archive_files(
+            path,
+            ArchiveKind::TarGz,
+            &overrides,
+            &true,
+            &AnalysisOptions::default(),
+        )
+        .unwrap();
+        let files = files
+            .iter()
+            .map(|x| (x.path.as_str(), x.file_type, x.loc))
+            .collect::<Vec<_>>();
+        assert_eq!(files, [("release/src/main.rs", FileType::Rust, 3)]);
+
+        let contents = get_archive_contents(path, ArchiveKind::TarGz, &overrides).unwrap();
+        assert_eq!(contents.len(), 2);
+        assert_eq!(
+            contents["release/src/main.rs"],
+            "fn main() {\n    run();\n}\n"
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::file::{File, FileType};
+
+const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    pub path: String,
+    pub file_type: FileType,
+    pub loc: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commits: Option<usize>,
+}
+
+/// The analysis of a directory at one point in time, saved with `--save-baseline`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub dir: String,
+    pub files: Vec<SnapshotFile>,
+}
+
+impl Snapshot {
+    pub fn new(files: &[File], dir: &str) -> Self {
+        let mut files = files
+            .iter()
+            .map(|x| SnapshotFile {
+                path: x.path.clone(),
+                file_type: x.file_type,
+                loc: x.loc,
+                commits: x.commits,
+            })
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            dir: dir.to_string(),
+            files,
+        }
+    }
+}
+
+/// A loaded snapshot to compare the current files against.
+#[derive(Debug)]
+pub struct Baseline {
+    files: HashMap<String, usize>,
+    file_types: HashMap<FileType, (usize, usize)>,
+}
+
+impl Baseline {
+    pub fn new(snapshot: Snapshot) -> Self {
+        let mut file_types: HashMap<FileType, (usize, usize)> = HashMap::new();
+        for file in snapshot.files.iter() {
+            let (files, loc) = file_types.entry(file.file_type).or_insert((0, 0));
+            *files += 1;
+            *loc += file.loc;
+        }
+
+        Baseline {
+            files: snapshot
+                .files
+                .into_iter()
+                .map(|x| (x.path, x.loc))
+                .collect(),
+            file_types,
+        }
+    }
+
+    /// Lines of code of the file in the baseline, `None` for files that are new since then.
+    pub fn file_loc(&self, path: &str) -> Option<usize> {
+        self.files.get(path).copied()
+    }
+
+    /// Number of files and lines of code of a file type in the baseline.
+    pub fn file_type_stats(&self, file_type: &FileType) -> (usize, usize) {
+        self.file_types.get(file_type).copied().unwrap_or((0, 0))
+    }
+
+    pub fn total_files(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn total_loc(&self) -> usize {
+        self.files.values().sum()
+    }
+
+    /// Lines of code of all baseline files whose path matches the predicate.
+    pub fn loc_matching(&self, predicate: impl Fn(&str) -> bool) -> usize {
+        self.files
+            .iter()
+            .filter(|(path, _)| predicate(path))
+            .map(|(_, loc)| loc)
+            .sum()
+    }
+
+    pub fn removed_files(&self, files: &[File]) -> usize {
+        let current = files
+            .iter()
+            .map(|x| x.path.as_str())
+            .collect::<HashSet<_>>();
+        self.files
+            .keys()
+            .filter(|x| !current.contains(&x.as_str()))
+            .count()
+    }
+}
+
+pub fn save_baseline(path: &str, files: &[File], dir: &str) -> Result<()> {
+    let snapshot = Snapshot::new(files, dir);
+    let content = serde_json::to_string_pretty(&snapshot)?;
+
+    fs::write(path, content + "\n").with_context(|| format!("could not write baseline {path}"))
+}
+
+pub fn load_baseline(path: &str) -> Result<Baseline> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("could not read baseline {path}"))?;
+    let snapshot: Snapshot =
+        serde_json::from_str(&content).with_context(|| format!("invalid baseline {path}"))?;
+
+    if snapshot.version != SNAPSHOT_VERSION {
+        bail!(
+            "baseline {path} has version {}, expected {SNAPSHOT_VERSION}",
+            snapshot.version
+        );
+    }
+
+    Ok(Baseline::new(snapshot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::file_from_content;
+
+    #[test]
+    fn test_baseline() {
+        let files = vec![
+            file_from_content("src/main.rs", "fn main() {\n}\n"),
+            file_from_content("src/lib.rs", "pub mod a;\npub mod b;\npub mod c;\n"),
+            file_from_content("index.js", "console.log(1)\n"),
+        ];
+        let content = serde_json::to_string(&Snapshot::new(&files, ".")).unwrap();
+        let baseline = Baseline::new(serde_json::from_str(&content).unwrap());
+
+        assert_eq!(baseline.file_loc("src/main.rs"), Some(2));
+        assert_eq!(baseline.file_loc("src/new.rs"), None);
+        assert_eq!(baseline.file_type_stats(&FileType::Rust), (2, 5));
+        assert_eq!(baseline.file_type_stats(&FileType::Go), (0, 0));
+        assert_eq!(baseline.total_loc(), 6);
+        assert_eq!(baseline.removed_files(&files[..2]), 1);
+    }
+}
@@ -0,0 +1,232 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    file::{content_hash, AnalysisOptions, File},
+    git::GitCache,
+};
+
+const CACHE_VERSION: u32 = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedFile {
+    pub size: u64,
+    /// Modification time in nanoseconds since the unix epoch
+    pub modified: u128,
+    pub file: File,
+}
+
+/// Analysis results of an earlier run, saved under `.git/code-peek` or the XDG cache directory.
+/// Files are reused while their size and modification time stay the same, or while their
+/// content hash does, and the git history while HEAD does not change.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cache {
+    /// Version and analysis options the files were analyzed with
+    pub key: String,
+    pub files: HashMap<String, CachedFile>,
+    /// Start of the run that cached the files, in whole seconds since the unix epoch. Files
+    /// modified since then may have changed after they were read.
+    #[serde(default)]
+    pub written: u64,
+    pub git: GitCache,
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    started: u64,
+}
+
+impl Cache {
+    /// Loads the cache of `dir`. Files analyzed with other options are dropped, a missing or
+    /// unreadable cache starts empty.
+    pub fn load(dir: &str, analysis: &AnalysisOptions) -> Self {
+        let path = cache_path(dir);
+        let key = cache_key(analysis);
+        let mut cache = fs::read_to_string(&path)
+            .ok()
+            .and_then(|x| serde_json::from_str::<Cache>(&x).ok())
+            .unwrap_or_default();
+        if cache.key != key {
+            cache.files.clear();
+            cache.key = key;
+        }
+        cache.path = path;
+        // whole seconds, as coarse file systems round the modification time down to them
+        cache.started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or(0);
+        cache
+    }
+
+    /// The cached file at `path`, if its size and modification time did not change. Files
+    /// modified during the cached run are racily clean, a change in the same tick of the clock
+    /// keeps the modification time, so only [`Cache::get_by_content`] can tell.
+    pub fn get(&self, path: &str, metadata: &fs::Metadata) -> Option<&File> {
+        let cached = self.files.get(path)?;
+        let racy = cached.modified >= u128::from(self.written) * 1_000_000_000;
+        (!racy && cached.size == metadata.len() && Some(cached.modified) == modified(metadata))
+            .then_some(&cached.file)
+    }
+
+    /// The cached file at `path`, if its content did not change.
+    pub fn get_by_content(&self, path: &str, content: &str) -> Option<&File> {
+        let cached = self.files.get(path)?;
+        (cached.file.hash == content_hash(content)).then_some(&cached.file)
+    }
+
+    /// Replaces the cached files with the analyzed files of `dir`, without the git
+    /// information, which is cached by commit instead.
+    pub fn update_files(&mut self, dir: &str, files: &[File]) {
+        self.written = self.started;
+        self.files = files
+            .iter()
+            .filter_map(|file| {
+                let metadata = fs::metadata(Path::new(dir).join(&file.path)).ok()?;
+                let mut file = file.clone();
+                file.commits = None;
+                file.churn = None;
+                file.age = None;
+                for todo in file.todos.iter_mut().flatten() {
+                    todo.blame = None;
+                }
+                let cached = CachedFile {
+                    size: metadata.len(),
+                    modified: modified(&metadata)?,
+                    file,
+                };
+                Some((cached.file.path.clone(), cached))
+            })
+            .collect();
+    }
+
+    /// Writes the cache, a failure is only reported as the cache is an optimization.
+    pub fn save(&self) {
+        if let Err(err) = self.write() {
+            eprintln!("WARNING: could not save the cache: {err:#}");
+        }
+    }
+
+    fn write(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("could not create {}", parent.display()))?;
+        }
+        let content = serde_json::to_string(self)?;
+        fs::write(&self.path, content)
+            .with_context(|| format!("could not write {}", self.path.display()))
+    }
+}
+
+fn modified(metadata: &fs::Metadata) -> Option<u128> {
+    let modified = metadata.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+fn cache_key(analysis: &AnalysisOptions) -> String {
+    format!(
+        "{CACHE_VERSION}-{}-{}-{}{}{}{}-{}",
+        env!("CARGO_PKG_VERSION"),
+        // the grammars count other lines than the line based counter
+        cfg!(feature = "tree-sitter") as u8,
+        analysis.complexity as u8,
+        analysis.source as u8,
+        analysis.symbols as u8,
+        analysis.tests as u8,
+        analysis
+            .todos
+            .as_ref()
+            .map(|x| x.as_str())
+            .unwrap_or_default()
+    )
+}
+
+// inside the git directory of a repository, so it is never counted or committed, and in the
+// user's cache directory for everything else. Named after the directory, as subdirectories of
+// one repository share its git directory.
+fn cache_path(dir: &str) -> PathBuf {
+    let canonical_dir = fs::canonicalize(dir).unwrap_or_else(|_| PathBuf::from(dir));
+    let name = format!(
+        "{:016x}.json",
+        content_hash(&canonical_dir.to_string_lossy())
+    );
+
+    let git_dir = Command::new("git")
+        .args(["rev-parse", "--absolute-git-dir"])
+        .current_dir(dir)
+        .output()
+        .ok()
+        .filter(|x| x.status.success())
+        .map(|x| String::from_utf8_lossy(&x.stdout).trim().to_string());
+    if let Some(git_dir) = git_dir {
+        return Path::new(&git_dir).join("code-peek").join(name);
+    }
+
+    let cache_dir = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|x| Path::new(&x).join(".cache")))
+        .unwrap_or_else(env::temp_dir);
+    cache_dir.join("code-peek").join(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{file::get_files, temp_dir::TempDir};
+    use ignore::overrides::Override;
+    use std::time::Duration;
+
+    #[test]
+    fn test_cache() {
+        let dir = TempDir::new("cache");
+        dir.write("main.rs", "fn main() {}\n");
+        // older than the run, so the modification time can be trusted
+        fs::File::options()
+            .write(true)
+            .open(dir.join("main.rs"))
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(3600))
+            .unwrap();
+        let analysis = AnalysisOptions::default();
+
+        let mut cache = Cache::load(dir.to_str(), &analysis);
+        cache.path = dir.join("cache.json");
+        let mut files = get_files(dir.to_str(), Override::empty(), &false, &analysis, None);
+        files[0].commits = Some(3);
+        cache.update_files(dir.to_str(), &files);
+
+        let metadata = fs::metadata(dir.join("main.rs")).unwrap();
+        let cached = cache.get("main.rs", &metadata).unwrap();
+        assert_eq!((cached.loc, cached.commits), (1, None));
+        assert!(cache.get_by_content("main.rs", "fn main() {}\n").is_some());
+        assert!(cache
+            .get_by_content("main.rs", "fn main() {}\n\n")
+            .is_none());
+
+        // written during the run, a later change could keep the same modification time
+        dir.write("main.rs", "fn main() {}\n");
+        cache.update_files(dir.to_str(), &files);
+        let metadata = fs::metadata(dir.join("main.rs")).unwrap();
+        assert!(cache.get("main.rs", &metadata).is_none());
+        assert!(cache.get_by_content("main.rs", "fn main() {}\n").is_some());
+
+        cache.save();
+        let saved = fs::read_to_string(dir.join("cache.json")).unwrap();
+        let loaded = serde_json::from_str::<Cache>(&saved).unwrap();
+        assert_eq!(loaded.files.len(), 1);
+        assert_eq!(loaded.key, cache.key);
+        assert_eq!(loaded.written, cache.started);
+
+        // every directory of a repository has a cache of its own
+        let src = dir.join("src");
+        fs::create_dir(&src).unwrap();
+        assert_ne!(cache_path(dir.to_str()), cache_path(src.to_str().unwrap()));
+    }
+}
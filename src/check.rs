@@ -0,0 +1,494 @@
+use std::{collections::BTreeMap, fs};
+
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use globset::{Glob, GlobMatcher};
+use serde::Deserialize;
+
+use crate::{
+    baseline::Baseline,
+    cli::CheckOptions,
+    file::{File, FileType},
+    sarif::sarif_report,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckFormat {
+    Text,
+    Sarif,
+}
+
+/// Exit code used when at least one rule with level error is violated, distinct from panics and usage errors.
+pub const EXIT_VIOLATIONS: i32 = 3;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    #[default]
+    Error,
+    Warning,
+    Note,
+}
+
+impl Level {
+    pub fn id(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Note => "note",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    /// Severity of the violations of these limits, only errors make the check fail
+    pub level: Option<Level>,
+    pub max_loc_per_file: Option<usize>,
+    pub max_files: Option<usize>,
+    pub max_total_loc: Option<usize>,
+    /// Maximum growth of the total lines of code in percent, compared to `--baseline`
+    pub max_growth: Option<f64>,
+    /// Maximum number of lines added and removed per file over the whole git history
+    pub max_churn: Option<usize>,
+    pub max_commits: Option<usize>,
+    /// Keys that are not a limit, `deny_unknown_fields` does not work with `flatten`
+    #[serde(flatten)]
+    pub unknown: BTreeMap<String, toml::Value>,
+}
+
+impl Limits {
+    // a misspelt limit would otherwise never be checked and the check always pass
+    fn check_unknown(&self, scope: &str) -> Result<()> {
+        match self.unknown.keys().next() {
+            Some(key) => bail!("unknown rule {key} for {scope}"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GlobRule {
+    pub glob: String,
+    #[serde(flatten)]
+    pub limits: Limits,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Rules {
+    #[serde(flatten)]
+    pub limits: Limits,
+    pub file_types: BTreeMap<FileType, Limits>,
+    pub globs: Vec<GlobRule>,
+}
+
+impl Rules {
+    fn all_limits(&self) -> impl Iterator<Item = &Limits> {
+        std::iter::once(&self.limits)
+            .chain(self.file_types.values())
+            .chain(self.globs.iter().map(|x| &x.limits))
+    }
+
+    pub fn needs_commits(&self) -> bool {
+        self.all_limits().any(|x| x.max_commits.is_some())
+    }
+
+    pub fn needs_churn(&self) -> bool {
+        self.all_limits().any(|x| x.max_churn.is_some())
+    }
+
+    pub fn needs_baseline(&self) -> bool {
+        self.all_limits().any(|x| x.max_growth.is_some())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RuleId {
+    MaxLocPerFile,
+    MaxFiles,
+    MaxTotalLoc,
+    MaxGrowth,
+    MaxChurn,
+    MaxCommits,
+}
+
+impl RuleId {
+    pub const ALL: [RuleId; 6] = [
+        RuleId::MaxLocPerFile,
+        RuleId::MaxFiles,
+        RuleId::MaxTotalLoc,
+        RuleId::MaxGrowth,
+        RuleId::MaxChurn,
+        RuleId::MaxCommits,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            RuleId::MaxLocPerFile => "A file has more lines of code than allowed",
+            RuleId::MaxFiles => "A group of files has more files than allowed",
+            RuleId::MaxTotalLoc => "A group of files has more lines of code than allowed",
+            RuleId::MaxGrowth => "The lines of code grew more than allowed since the baseline",
+            RuleId::MaxChurn => "A file had more lines added and removed than allowed",
+            RuleId::MaxCommits => "A file was changed in more commits than allowed",
+        }
+    }
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            RuleId::MaxLocPerFile => "max-loc-per-file",
+            RuleId::MaxFiles => "max-files",
+            RuleId::MaxTotalLoc => "max-total-loc",
+            RuleId::MaxGrowth => "max-growth",
+            RuleId::MaxChurn => "max-churn",
+            RuleId::MaxCommits => "max-commits",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub rule: RuleId,
+    pub level: Level,
+    /// The offending file, `None` for rules about a whole group of files
+    pub path: Option<String>,
+    pub message: String,
+}
+
+struct Scope<'a> {
+    name: String,
+    files: Vec<&'a File>,
+    baseline_loc: Option<usize>,
+}
+
+impl Scope<'_> {
+    /// How the files of the scope are called in messages, e.g. "Rust files"
+    fn files_label(&self) -> &str {
+        self.name.strip_prefix("all ").unwrap_or(&self.name)
+    }
+}
+
+pub fn load_rules(path: &str) -> Result<Rules> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("could not read rules file {path}"))?;
+
+    toml::from_str(&content).with_context(|| format!("invalid rules file {path}"))
+}
+
+pub fn check_rules(
+    files: &[File],
+    rules: &Rules,
+    baseline: Option<&Baseline>,
+) -> Result<Vec<Violation>> {
+    let mut violations = Vec::new();
+
+    let scope = Scope {
+        name: String::from("all files"),
+        files: files.iter().collect(),
+        baseline_loc: baseline.map(|x| x.total_loc()),
+    };
+    rules.limits.check_unknown(&scope.name)?;
+    violations.extend(check_limits(&scope, &rules.limits));
+
+    for (file_type, limits) in rules.file_types.iter() {
+        let scope = Scope {
+            name: format!("{file_type} files"),
+            files: files.iter().filter(|x| x.file_type == *file_type).collect(),
+            baseline_loc: baseline.map(|x| x.file_type_stats(file_type).1),
+        };
+        limits.check_unknown(&scope.name)?;
+        violations.extend(check_limits(&scope, limits));
+    }
+
+    for rule in rules.globs.iter() {
+        let matcher: GlobMatcher = Glob::new(&rule.glob)
+            .with_context(|| format!("invalid glob {}", rule.glob))?
+            .compile_matcher();
+        let scope = Scope {
+            name: format!("files matching {}", rule.glob),
+            files: files.iter().filter(|x| matcher.is_match(&x.path)).collect(),
+            baseline_loc: baseline.map(|x| x.loc_matching(|path| matcher.is_match(path))),
+        };
+        rule.limits.check_unknown(&scope.name)?;
+        violations.extend(check_limits(&scope, &rule.limits));
+    }
+
+    Ok(violations)
+}
+
+fn check_limits(scope: &Scope, limits: &Limits) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let level = limits.level.unwrap_or_default();
+    let total_loc = scope.files.iter().map(|x| x.loc).sum::<usize>();
+
+    for file in scope.files.iter() {
+        let per_file = [
+            (
+                RuleId::MaxLocPerFile,
+                limits.max_loc_per_file,
+                Some(file.loc),
+                "lines of code",
+            ),
+            (
+                RuleId::MaxChurn,
+                limits.max_churn,
+                file.churn,
+                "changed lines",
+            ),
+            (
+                RuleId::MaxCommits,
+                limits.max_commits,
+                file.commits,
+                "commits",
+            ),
+        ];
+        for (rule, limit, actual, unit) in per_file {
+            if let (Some(limit), Some(actual)) = (limit, actual) {
+                if actual > limit {
+                    violations.push(Violation {
+                        rule,
+                        level,
+                        path: Some(file.path.clone()),
+                        message: format!(
+                            "{} has {actual} {unit}, the limit for {} is {limit}",
+                            file.path, scope.name
+                        ),
+                    });
+                }
+            }
+        }
+    }
+
+    if let Some(limit) = limits.max_files {
+        if scope.files.len() > limit {
+            violations.push(Violation {
+                rule: RuleId::MaxFiles,
+                level,
+                path: None,
+                message: format!(
+                    "found {} {}, the limit is {limit}",
+                    scope.files.len(),
+                    scope.files_label()
+                ),
+            });
+        }
+    }
+
+    if let Some(limit) = limits.max_total_loc {
+        if total_loc > limit {
+            violations.push(Violation {
+                rule: RuleId::MaxTotalLoc,
+                level,
+                path: None,
+                message: format!(
+                    "{} have {total_loc} lines of code, the limit is {limit}",
+                    scope.name
+                ),
+            });
+        }
+    }
+
+    if let (Some(limit), Some(baseline_loc)) = (limits.max_growth, scope.baseline_loc) {
+        let growth = if baseline_loc == 0 {
+            if total_loc == 0 {
+                0.0
+            } else {
+                f64::INFINITY
+            }
+        } else {
+            (total_loc as f64 - baseline_loc as f64) * 100.0 / baseline_loc as f64
+        };
+        if growth > limit {
+            violations.push(Violation {
+                rule: RuleId::MaxGrowth,
+                level,
+                path: None,
+                message: format!(
+                    "{} grew by {growth:.1}% ({baseline_loc} -> {total_loc} lines of code), the limit is {limit}%",
+                    scope.name
+                ),
+            });
+        }
+    }
+
+    violations
+}
+
+pub fn has_errors(violations: &[Violation]) -> bool {
+    violations.iter().any(|x| x.level == Level::Error)
+}
+
+pub fn report_violations(
+    violations: &[Violation],
+    options: &CheckOptions,
+    rules_path: Option<&str>,
+) -> Result<()> {
+    match options.format {
+        CheckFormat::Text => display_violations(violations),
+        CheckFormat::Sarif => {
+            let report = sarif_report(violations, rules_path);
+            let content = serde_json::to_string_pretty(&report)? + "\n";
+            match &options.output {
+                Some(path) => fs::write(path, content)
+                    .with_context(|| format!("could not write to {path}"))?,
+                None => print!("{content}"),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn display_violations(violations: &[Violation]) {
+    if violations.is_empty() {
+        println!("\n{}\n", "All checks passed".bright_green().bold());
+        return;
+    }
+
+    println!(
+        "\n{} {}\n",
+        violations.len().to_string().bright_red().bold(),
+        "rule violations found".bright_red().bold()
+    );
+    for violation in violations.iter() {
+        let marker = match violation.level {
+            Level::Error => "✗".bright_red(),
+            Level::Warning => "!".yellow(),
+            Level::Note => "i".blue(),
+        };
+        println!(
+            "{} {} {}",
+            marker,
+            violation.message,
+            format!("({})", violation.rule.id()).dimmed()
+        );
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::file_from_content;
+
+    #[test]
+    fn test_parse_rules() {
+        let rules: Rules = toml::from_str(
+            r#"
+            max_files = 100
+            max_loc_per_file = 1000
+
+            [file_types.Python]
+            max_growth = 5.0
+
+            [[globs]]
+            glob = "src/generated/**"
+            max_loc_per_file = 5000
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(rules.limits.max_files, Some(100));
+        assert_eq!(
+            rules.file_types.get(&FileType::Python).unwrap().max_growth,
+            Some(5.0)
+        );
+        assert_eq!(rules.globs[0].glob, "src/generated/**");
+        assert_eq!(rules.globs[0].limits.max_loc_per_file, Some(5000));
+        assert!(!rules.needs_churn());
+    }
+
+    #[test]
+    fn test_check_rules() {
+        let mut files = vec![
+            file_from_content("src/main.rs", &"x\n".repeat(20)),
+            file_from_content("src/generated/api.rs", &"x\n".repeat(50)),
+            file_from_content("app.py", &"x\n".repeat(11)),
+        ];
+        files[0].add_churn(300);
+
+        let rules = Rules {
+            limits: Limits {
+                max_loc_per_file: Some(10),
+                max_churn: Some(200),
+                ..Default::default()
+            },
+            file_types: BTreeMap::from([(
+                FileType::Rust,
+                Limits {
+                    max_files: Some(1),
+                    ..Default::default()
+                },
+            )]),
+            globs: vec![GlobRule {
+                glob: String::from("src/generated/**"),
+                limits: Limits {
+                    max_loc_per_file: Some(40),
+                    ..Default::default()
+                },
+            }],
+        };
+
+        let violations = check_rules(&files, &rules, None).unwrap();
+        assert_eq!(
+            violations
+                .iter()
+                .map(|x| (x.rule, x.path.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                (RuleId::MaxLocPerFile, Some("src/main.rs")),
+                (RuleId::MaxChurn, Some("src/main.rs")),
+                (RuleId::MaxLocPerFile, Some("src/generated/api.rs")),
+                (RuleId::MaxLocPerFile, Some("app.py")),
+                (RuleId::MaxFiles, None),
+                (RuleId::MaxLocPerFile, Some("src/generated/api.rs")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_growth() {
+        let files = vec![file_from_content("app.py", &"x\n".repeat(106))];
+        let content = r#"{"version": 1, "dir": ".", "files": [{"path": "app.py", "file_type": "Python", "loc": 100}]}"#;
+        let baseline = Baseline::new(serde_json::from_str(content).unwrap());
+
+        let rules = Rules {
+            file_types: BTreeMap::from([(
+                FileType::Python,
+                Limits {
+                    max_growth: Some(5.0),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+
+        let violations = check_rules(&files, &rules, Some(&baseline)).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, RuleId::MaxGrowth);
+        assert!(check_rules(&files, &rules, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_rules() {
+        let files = vec![file_from_content("app.py", &"x\n".repeat(10))];
+        let rules: Rules = toml::from_str(
+            r#"
+            max_loc_per_fil = 2
+
+            [[globs]]
+            glob = "*.py"
+            max_files = 1
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            check_rules(&files, &rules, None).unwrap_err().to_string(),
+            "unknown rule max_loc_per_fil for all files"
+        );
+
+        let rules: Rules = toml::from_str("[file_types.Python]\nmax_comits = 5\n").unwrap();
+        assert!(check_rules(&files, &rules, None).is_err());
+    }
+}
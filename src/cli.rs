@@ -1,15 +1,79 @@
 use std::env;
 
-use anyhow::Result;
-use clap::{arg, command, crate_version};
+use anyhow::{bail, Result};
+use clap::{arg, command, crate_version, ArgAction, ArgMatches, Command};
+
+use crate::{
+    archive::ArchiveKind,
+    check::CheckFormat,
+    columns::{Column, SortKey, SortOrder, TableOptions},
+    diff::DiffFormat,
+    duplicates::DuplicatesFormat,
+    git::{is_git_repository, AuthorGrouping},
+    history::{HistoryFormat, Sampling},
+    submodules::SubmoduleMode,
+};
 
 #[derive(Debug)]
 pub struct Cli {
     pub dir: String,
+    pub dirs: Vec<String>,
+    pub manifest: Option<String>,
+    pub config: Option<String>,
     pub display_options: DisplayOptions,
     pub exclude: Vec<String>,
     pub matches: Vec<String>,
     pub num: usize,
+    pub author_grouping: Option<AuthorGrouping>,
+    pub exclude_authors: Vec<String>,
+    pub skip_bots: bool,
+    pub todo_markers: Vec<String>,
+    pub watch: bool,
+    pub no_cache: bool,
+    pub submodules: Option<SubmoduleMode>,
+    pub save_baseline: Option<String>,
+    pub baseline: Option<String>,
+    pub command: Option<SubCommand>,
+}
+
+#[derive(Debug)]
+pub enum SubCommand {
+    History(HistoryOptions),
+    Diff(DiffOptions),
+    Check(CheckOptions),
+    Duplicates(DuplicatesOptions),
+    Tui,
+}
+
+#[derive(Debug)]
+pub struct HistoryOptions {
+    pub rev: String,
+    pub sampling: Sampling,
+    pub format: HistoryFormat,
+    pub output: Option<String>,
+    pub file_types: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct CheckOptions {
+    pub rules: Option<String>,
+    pub format: CheckFormat,
+    pub output: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct DuplicatesOptions {
+    pub min_lines: usize,
+    pub format: DuplicatesFormat,
+    pub output: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct DiffOptions {
+    pub from: String,
+    pub to: String,
+    pub format: DiffFormat,
+    pub output: Option<String>,
 }
 
 #[derive(Debug)]
@@ -18,6 +82,19 @@ pub struct DisplayOptions {
     pub git: bool,
     pub all: bool,
     pub skip_lockfiles: bool,
+    pub tracked_only: bool,
+    pub untracked: bool,
+    pub age: bool,
+    pub complexity: bool,
+    pub breakdown: bool,
+    pub symbols: bool,
+    pub tests: bool,
+    pub todos: bool,
+    pub identical: bool,
+    pub unique: bool,
+    pub bytes: bool,
+    pub rev: Option<String>,
+    pub table: TableOptions,
 }
 
 pub fn run_cli() -> Result<Cli> {
@@ -28,30 +105,144 @@ pub fn run_cli() -> Result<Cli> {
       .name("Code Peek")
       .version(crate_version!())
       .about("A CLI tool to peek into codebases and gather insights")
-      .arg(arg!([directory] -d --dir [DIRECTORY] "Directory to search, defauls to cwd").required(false))
-      .arg(arg!([num] -n --num [NUMBER]  "Number of files to display, defauls to 10").required(false))
+      .arg(arg!(directory: -d --dir [DIRECTORY] "Directory to search, defauls to cwd. Can also be a .tar, .tar.gz, .tgz or .zip archive. Repeat it to combine several repositories").required(false).global(true).action(ArgAction::Append))
+      .arg(arg!(--manifest [FILE] "File listing the repositories to combine, one directory per line").required(false))
+      .arg(arg!(num: -n --num [NUMBER]  "Number of files to display, defauls to 10").required(false).global(true))
       .arg(
-          arg!([exclude]
+          arg!(exclude:
             -e --exclude [GLOB] ... "Globs to exclude other than the files in the .gitignore, expects a comma separated list. E.g. '*.txt,*.csv'"
         )
-        .required(false),
+        .required(false)
+        .global(true),
     )
-    .arg(arg!([all] -a --all "Display all available information").required(false))
-    .arg(arg!([group] -g --group "Group the results by its extension").required(false))
-    .arg(arg!(--"skip-lockfiles" "Skips lockfiles in analysis").long("skip-lockfiles").required(false))
-    .arg(arg!([git] -t --git "Get git info - how many commits were made to each file").required(false))
+    .arg(arg!(all: -a --all "Display all available information").required(false))
+    .arg(arg!(group: -g --group "Group the results by its extension").required(false))
+    .arg(arg!(--"skip-lockfiles" "Skips lockfiles in analysis").long("skip-lockfiles").required(false).global(true))
+    .arg(arg!(git: -t --git "Get git info - how many commits were made to each file").required(false))
       .arg(
-          arg!([match]
+          arg!(match:
               -m --match [GLOB] ... "Globs to check, expects a comma separated list. E.g. '*.txt,*.csv' (Only files that match the pattern will be processed)"
           )
-          .required(false),
+          .required(false)
+          .global(true),
       )
+    .arg(arg!(--age "Show how old the code is based on the git log and git blame (not included in --all, as blaming every file takes a while)").required(false))
+    .arg(arg!(--complexity "Estimate the cyclomatic complexity of files and functions by counting decision points").required(false))
+    .arg(arg!(--breakdown "Split the lines into code, comments and blanks and count functions, classes and imports per file type").required(false))
+    .arg(arg!(--symbols "Count functions, types, traits and tests per file type and list the largest functions").required(false))
+    .arg(arg!(--tests "Split the code into test and production code and show the test to code ratio per file type and directory").required(false))
+    .arg(arg!(--todos "List the TODO, FIXME, HACK and XXX markers in comments, with their author and age if git info is enabled").required(false))
+    .arg(arg!(--identical "List groups of identical files, with the same size and content hash, and the lines of code wasted on the copies").required(false))
+    .arg(arg!(--unique "Count identical files only once in the totals and tables").required(false))
+    .arg(arg!(--bytes "List the largest files by size in bytes with their line lengths, and flag minified files").required(false))
+    .arg(
+        arg!(--"sort-by" [KEY] "Sort the file tables by this key, each table defaults to its own key (lines of code for most of them)")
+            .value_parser(["loc", "commits", "churn", "bytes", "name", "path", "age", "todos", "functions"])
+            .required(false)
+            .global(true),
+    )
+    .arg(arg!(--asc "Sort in ascending order, the default for name and path").required(false).global(true).conflicts_with("desc"))
+    .arg(arg!(--desc "Sort in descending order, the default for numbers and age").required(false).global(true))
+    .arg(arg!(--columns [COLUMNS] "Columns of the file tables in this order, expects a comma separated list of path, name, type, loc, bytes, commits, churn, age, change, complexity, longest-line, line-length, last-commit, line-age, todos, functions, types, traits and tests").required(false).global(true))
+    .arg(arg!(--"num-files" [NUMBER] "Number of files in each file table, defaults to --num").required(false).global(true))
+    .arg(arg!(--"num-groups" [NUMBER] "Number of file types in the grouped output, defaults to all").required(false).global(true))
+    .arg(arg!(--"min-loc" [NUMBER] "Leave files and file types with fewer lines of code out of the tables").required(false).global(true))
+    .arg(arg!(--"todo-markers" [MARKERS] "Additional markers for --todos, expects a comma separated list. E.g. 'PERF,SAFETY'").required(false))
+    .arg(arg!(--watch "Keep running and update the summary whenever files are created, changed or deleted").required(false))
+    .arg(arg!(--"tracked-only" "Only analyze files in the git index, leaving out untracked files that are not ignored").required(false).global(true))
+    .arg(
+        arg!(--submodules [MODE] "Leave the files of submodules out, report each submodule separately or count it with its own git history")
+            .value_parser(["exclude", "separate", "recurse"])
+            .required(false)
+            .conflicts_with("rev"),
+    )
+    .arg(arg!(--untracked "Leave untracked files out of the analysis and list them separately").required(false).conflicts_with("tracked-only"))
+    .arg(arg!(--rev [REV] "Analyze the files of a commit, branch or tag instead of the working tree").required(false).global(true))
+    .arg(arg!(--"no-cache" "Analyze every file and the whole git history again instead of reusing the results of earlier runs").required(false).global(true))
+    .arg(arg!(--config [FILE] "Path to a config file, defaults to .code-peek.toml in the searched directory").required(false).global(true))
+    .arg(
+        arg!(--"authors-by" [KEY] "Group git authors by name, email or email domain")
+            .value_parser(["name", "email", "domain"])
+            .required(false),
+    )
+    .arg(
+        arg!(--"exclude-authors" [PATTERN] "Regular expressions for authors to leave out, expects a comma separated list. E.g. 'dependabot,renovate'")
+            .required(false),
+    )
+    .arg(arg!(--"skip-bots" "Skips bot accounts like dependabot and renovate in the author ranking").required(false))
+    .arg(arg!(--"save-baseline" [FILE] "Save a snapshot of the analysis as JSON to compare against later").required(false))
+    .arg(arg!(--baseline [FILE] "Compare the analysis against a snapshot saved with --save-baseline").required(false).global(true))
+    .subcommand(
+        Command::new("history")
+            .about("Show how the lines of code per file type changed over time, without checking anything out")
+            .arg(arg!(--branch [REV] "Branch or revision whose history is sampled, defaults to HEAD").required(false))
+            .arg(arg!(--every [NUMBER] "Sample every n-th commit, defaults to 10").required(false))
+            .arg(arg!(--weekly "Sample one commit per week instead of every n-th commit").required(false).conflicts_with("every"))
+            .arg(
+                arg!(--format [FORMAT] "Output format, defaults to an ASCII chart")
+                    .value_parser(["chart", "json", "csv", "svg"])
+                    .required(false),
+            )
+            .arg(arg!(-o --output [FILE] "Write the output to a file instead of stdout").required(false))
+            .arg(arg!(--types [TYPES] "File types to include, expects a comma separated list. E.g. 'TypeScript,JavaScript'").required(false)),
+    )
+    .subcommand(
+        Command::new("diff")
+            .about("Compare the files of two git revisions, read straight from the object database")
+            .arg(arg!(<FROM> "Revision to compare from, e.g. a release tag"))
+            .arg(arg!([TO] "Revision to compare to, defaults to HEAD"))
+            .arg(
+                arg!(--format [FORMAT] "Output format, defaults to tables")
+                    .value_parser(["table", "json", "markdown"])
+                    .required(false),
+            )
+            .arg(arg!(-o --output [FILE] "Write the output to a file instead of stdout").required(false)),
+    )
+    .subcommand(
+        Command::new("check")
+            .about("Check the files against the limits of a rules file and exit with code 3 if any are exceeded")
+            .arg(arg!(--rules [FILE] "Rules file, defaults to the [check] section of the config file").required(false))
+            .arg(
+                arg!(--format [FORMAT] "Output format, sarif can be uploaded to GitHub code scanning")
+                    .value_parser(["text", "sarif"])
+                    .required(false),
+            )
+            .arg(arg!(-o --output [FILE] "Write the output to a file instead of stdout").required(false)),
+    )
+    .subcommand(
+        Command::new("tui")
+            .about("Explore the languages, files, directories and contributors in an interactive terminal UI"),
+    )
+    .subcommand(
+        Command::new("duplicates")
+            .about("Find blocks of code that were copied between files, ignoring whitespace and comments")
+            .arg(arg!(--"min-lines" [NUMBER] "Minimum number of lines of a duplicated block, defaults to 6").required(false))
+            .arg(
+                arg!(--format [FORMAT] "Output format, defaults to tables")
+                    .value_parser(["table", "json"])
+                    .required(false),
+            )
+            .arg(arg!(-o --output [FILE] "Write the output to a file instead of stdout").required(false)),
+    )
       .get_matches();
 
-    let dir = match matches.get_one::<String>("directory") {
-        Some(directory) => directory,
-        None => default_dir,
+    let command = match matches.subcommand() {
+        Some(("history", sub_matches)) => Some(SubCommand::History(history_options(sub_matches))),
+        Some(("diff", sub_matches)) => Some(SubCommand::Diff(diff_options(sub_matches))),
+        Some(("check", sub_matches)) => Some(SubCommand::Check(check_options(sub_matches))),
+        Some(("tui", _)) => Some(SubCommand::Tui),
+        Some(("duplicates", sub_matches)) => {
+            Some(SubCommand::Duplicates(duplicates_options(sub_matches)))
+        }
+        _ => None,
+    };
+
+    let dirs = match matches.get_many::<String>("directory") {
+        Some(directories) => directories.cloned().collect::<Vec<_>>(),
+        None => Vec::new(),
     };
+    let dir = dirs.first().map_or(default_dir, |x| x.as_str());
+    let manifest = matches.get_one::<String>("manifest").cloned();
 
     let num: usize = match matches.get_one::<String>("num") {
         Some(num) => num.parse::<usize>().unwrap(),
@@ -61,11 +252,151 @@ pub fn run_cli() -> Result<Cli> {
     let all = matches.get_one::<bool>("all").unwrap().to_owned();
     let group = all || matches.get_one::<bool>("group").unwrap().to_owned();
     let git = all || matches.get_one::<bool>("git").unwrap().to_owned();
+    let age = matches.get_one::<bool>("age").unwrap().to_owned();
+    let complexity = matches.get_one::<bool>("complexity").unwrap().to_owned();
+    let breakdown = matches.get_one::<bool>("breakdown").unwrap().to_owned();
+    let symbols = matches.get_one::<bool>("symbols").unwrap().to_owned();
+    let tests = matches.get_one::<bool>("tests").unwrap().to_owned();
+    let todos = matches.get_one::<bool>("todos").unwrap().to_owned();
+    let identical = matches.get_one::<bool>("identical").unwrap().to_owned();
+    let unique = matches.get_one::<bool>("unique").unwrap().to_owned();
+    let bytes = matches.get_one::<bool>("bytes").unwrap().to_owned();
+    let rev = matches.get_one::<String>("rev").cloned();
+    let watch = matches.get_one::<bool>("watch").unwrap().to_owned();
+    let no_cache = matches.get_one::<bool>("no-cache").unwrap().to_owned();
+    let submodules = matches
+        .get_one::<String>("submodules")
+        .map(|x| match x.as_str() {
+            "exclude" => SubmoduleMode::Exclude,
+            "separate" => SubmoduleMode::Separate,
+            _ => SubmoduleMode::Recurse,
+        });
+    let order = if *matches.get_one::<bool>("asc").unwrap() {
+        Some(SortOrder::Ascending)
+    } else if *matches.get_one::<bool>("desc").unwrap() {
+        Some(SortOrder::Descending)
+    } else {
+        None
+    };
+    let table = TableOptions {
+        sort_by: matches
+            .get_one::<String>("sort-by")
+            .map(|x| x.parse::<SortKey>())
+            .transpose()?,
+        order,
+        columns: matches
+            .get_one::<String>("columns")
+            .map(|x| x.split(',').map(|x| x.parse::<Column>()).collect())
+            .transpose()?,
+        num_files: match matches.get_one::<String>("num-files") {
+            Some(num_files) => num_files.parse::<usize>()?,
+            None => num,
+        },
+        num_groups: matches
+            .get_one::<String>("num-groups")
+            .map(|x| x.parse::<usize>())
+            .transpose()?,
+        min_loc: match matches.get_one::<String>("min-loc") {
+            Some(min_loc) => min_loc.parse::<usize>()?,
+            None => 0,
+        },
+    };
     let skip_lockfiles = matches
         .get_one::<bool>("skip-lockfiles")
         .unwrap()
         .to_owned();
 
+    let config = matches.get_one::<String>("config").cloned();
+
+    let author_grouping = matches
+        .get_one::<String>("authors-by")
+        .map(|key| match key.as_str() {
+            "email" => AuthorGrouping::Email,
+            "domain" => AuthorGrouping::Domain,
+            _ => AuthorGrouping::Name,
+        });
+
+    let exclude_authors = if let Some(patterns) = matches.get_one::<String>("exclude-authors") {
+        patterns
+            .split(",")
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>()
+    } else {
+        Vec::new()
+    };
+
+    let skip_bots = matches.get_one::<bool>("skip-bots").unwrap().to_owned();
+
+    let todo_markers = if let Some(markers) = matches.get_one::<String>("todo-markers") {
+        markers
+            .split(",")
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>()
+    } else {
+        Vec::new()
+    };
+
+    let tracked_only = matches.get_one::<bool>("tracked-only").unwrap().to_owned();
+    let untracked = matches.get_one::<bool>("untracked").unwrap().to_owned();
+    let save_baseline = matches.get_one::<String>("save-baseline").cloned();
+    let baseline = matches.get_one::<String>("baseline").cloned();
+
+    if ArchiveKind::from_path(dir).is_some()
+        && (watch || rev.is_some() || tracked_only || untracked)
+    {
+        bail!(
+            "--watch, --rev, --tracked-only and --untracked need a directory, {dir} is an archive"
+        );
+    }
+    // history and diff read their own revisions, watch follows the working tree
+    if rev.is_some()
+        && (watch || matches!(command, Some(SubCommand::History(_) | SubCommand::Diff(_))))
+    {
+        bail!("--rev can not be combined with --watch or the history and diff subcommands");
+    }
+    // the split into tracked and untracked files follows the index, not a revision
+    if tracked_only || untracked {
+        if rev.is_some() {
+            bail!("--tracked-only and --untracked can not be combined with --rev");
+        }
+        if !is_git_repository(dir) {
+            bail!("--tracked-only and --untracked need a git repository, {dir} is not one");
+        }
+    }
+    // the combined summary has no sections for the details of a single repository
+    let details = group
+        || git
+        || age
+        || complexity
+        || breakdown
+        || symbols
+        || tests
+        || todos
+        || identical
+        || unique
+        || bytes;
+    // its only tables are per repository, file type and author
+    let table_options = table.sort_by.is_some()
+        || table.order.is_some()
+        || table.columns.is_some()
+        || matches.get_one::<String>("num-files").is_some()
+        || table.num_groups.is_some()
+        || table.min_loc > 0;
+    if (dirs.len() > 1 || manifest.is_some())
+        && (command.is_some()
+            || watch
+            || rev.is_some()
+            || tracked_only
+            || untracked
+            || submodules.is_some()
+            || save_baseline.is_some()
+            || baseline.is_some()
+            || details
+            || table_options)
+    {
+        bail!("several repositories can only be combined in the summary, without subcommands, --watch, --rev, --tracked-only, --untracked, --submodules, baselines, -a, -g, -t, --age, --complexity, --breakdown, --symbols, --tests, --todos, --identical, --unique, --bytes, --sort-by, --asc, --desc, --columns, --num-files, --num-groups or --min-loc");
+    }
+
     let exclude = if let Some(globs) = matches.get_one::<String>("exclude") {
         globs
             .split(",")
@@ -86,16 +417,133 @@ pub fn run_cli() -> Result<Cli> {
 
     let cli = Cli {
         dir: dir.to_string(),
+        dirs,
+        manifest,
+        config,
         num,
         display_options: DisplayOptions {
             all,
             group,
             git,
             skip_lockfiles,
+            tracked_only,
+            untracked,
+            age,
+            complexity,
+            breakdown,
+            symbols,
+            tests,
+            todos,
+            identical,
+            unique,
+            bytes,
+            rev,
+            table,
         },
         exclude,
         matches,
+        author_grouping,
+        exclude_authors,
+        skip_bots,
+        todo_markers,
+        watch,
+        no_cache,
+        submodules,
+        save_baseline,
+        baseline,
+        command,
     };
 
     Ok(cli)
 }
+
+fn history_options(matches: &ArgMatches) -> HistoryOptions {
+    let rev = match matches.get_one::<String>("branch") {
+        Some(rev) => rev.to_string(),
+        None => String::from("HEAD"),
+    };
+
+    let sampling = if matches.get_one::<bool>("weekly").unwrap().to_owned() {
+        Sampling::Weekly
+    } else {
+        match matches.get_one::<String>("every") {
+            Some(every) => Sampling::Commits(every.parse::<usize>().unwrap()),
+            None => Sampling::Commits(10),
+        }
+    };
+
+    let format = match matches.get_one::<String>("format").map(|x| x.as_str()) {
+        Some("json") => HistoryFormat::Json,
+        Some("csv") => HistoryFormat::Csv,
+        Some("svg") => HistoryFormat::Svg,
+        _ => HistoryFormat::Chart,
+    };
+
+    let file_types = if let Some(types) = matches.get_one::<String>("types") {
+        types
+            .split(",")
+            .map(|x| x.trim().to_string())
+            .collect::<Vec<String>>()
+    } else {
+        Vec::new()
+    };
+
+    HistoryOptions {
+        rev,
+        sampling,
+        format,
+        output: matches.get_one::<String>("output").cloned(),
+        file_types,
+    }
+}
+
+fn diff_options(matches: &ArgMatches) -> DiffOptions {
+    let to = match matches.get_one::<String>("TO") {
+        Some(rev) => rev.to_string(),
+        None => String::from("HEAD"),
+    };
+
+    let format = match matches.get_one::<String>("format").map(|x| x.as_str()) {
+        Some("json") => DiffFormat::Json,
+        Some("markdown") => DiffFormat::Markdown,
+        _ => DiffFormat::Table,
+    };
+
+    DiffOptions {
+        from: matches.get_one::<String>("FROM").unwrap().to_string(),
+        to,
+        format,
+        output: matches.get_one::<String>("output").cloned(),
+    }
+}
+
+fn check_options(matches: &ArgMatches) -> CheckOptions {
+    let format = match matches.get_one::<String>("format").map(|x| x.as_str()) {
+        Some("sarif") => CheckFormat::Sarif,
+        _ => CheckFormat::Text,
+    };
+
+    CheckOptions {
+        rules: matches.get_one::<String>("rules").cloned(),
+        format,
+        output: matches.get_one::<String>("output").cloned(),
+    }
+}
+
+fn duplicates_options(matches: &ArgMatches) -> DuplicatesOptions {
+    let min_lines = match matches.get_one::<String>("min-lines") {
+        Some(lines) => lines.parse::<usize>().unwrap(),
+        None => 6,
+    };
+
+    let format = match matches.get_one::<String>("format").map(|x| x.as_str()) {
+        Some("json") => DuplicatesFormat::Json,
+        _ => DuplicatesFormat::Table,
+    };
+
+    DuplicatesOptions {
+        min_lines,
+        format,
+        output: matches.get_one::<String>("output").cloned(),
+    }
+}
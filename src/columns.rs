@@ -0,0 +1,314 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    fmt,
+    str::FromStr,
+};
+
+use anyhow::{bail, Error, Result};
+
+use crate::file::{File, FileType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Loc,
+    Commits,
+    Churn,
+    Bytes,
+    Name,
+    Path,
+    Age,
+    /// Todo markers in the comments
+    Todos,
+    Functions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+/// Column of a file table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Path,
+    Name,
+    Type,
+    Loc,
+    Bytes,
+    Commits,
+    Churn,
+    Age,
+    /// Change in lines of code compared to the baseline
+    Change,
+    /// Max and average complexity, as two cells
+    Complexity,
+    /// Bytes in the longest line
+    LongestLine,
+    /// Average bytes per line
+    LineLength,
+    /// Date of the last commit
+    LastCommit,
+    /// Median age of the lines
+    LineAge,
+    Todos,
+    Functions,
+    Types,
+    Traits,
+    Tests,
+}
+
+/// Sort key, order, columns and limits chosen for the file tables, each table has its own
+/// default sort key and columns.
+#[derive(Debug, Default, Clone)]
+pub struct TableOptions {
+    pub sort_by: Option<SortKey>,
+    pub order: Option<SortOrder>,
+    pub columns: Option<Vec<Column>>,
+    /// Rows of every file table
+    pub num_files: usize,
+    /// File types of the grouped output, all if `None`
+    pub num_groups: Option<usize>,
+    /// Files and file types with fewer lines of code are left out
+    pub min_loc: usize,
+}
+
+const SORT_KEYS: [(&str, SortKey); 9] = [
+    ("loc", SortKey::Loc),
+    ("commits", SortKey::Commits),
+    ("churn", SortKey::Churn),
+    ("bytes", SortKey::Bytes),
+    ("name", SortKey::Name),
+    ("path", SortKey::Path),
+    ("age", SortKey::Age),
+    ("todos", SortKey::Todos),
+    ("functions", SortKey::Functions),
+];
+
+const COLUMNS: [(&str, Column); 19] = [
+    ("path", Column::Path),
+    ("name", Column::Name),
+    ("type", Column::Type),
+    ("loc", Column::Loc),
+    ("bytes", Column::Bytes),
+    ("commits", Column::Commits),
+    ("churn", Column::Churn),
+    ("age", Column::Age),
+    ("change", Column::Change),
+    ("complexity", Column::Complexity),
+    ("longest-line", Column::LongestLine),
+    ("line-length", Column::LineLength),
+    ("last-commit", Column::LastCommit),
+    ("line-age", Column::LineAge),
+    ("todos", Column::Todos),
+    ("functions", Column::Functions),
+    ("types", Column::Types),
+    ("traits", Column::Traits),
+    ("tests", Column::Tests),
+];
+
+impl FromStr for SortKey {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match SORT_KEYS.iter().find(|(name, _)| *name == value) {
+            Some((_, key)) => Ok(*key),
+            None => bail!("unknown sort key {value}"),
+        }
+    }
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (name, _) = SORT_KEYS.iter().find(|(_, key)| key == self).unwrap();
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Column {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match COLUMNS.iter().find(|(name, _)| *name == value.trim()) {
+            Some((_, column)) => Ok(*column),
+            None => bail!(
+                "unknown column {value}, expected one of {}",
+                COLUMNS.map(|(name, _)| name).join(", ")
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (name, _) = COLUMNS.iter().find(|(_, column)| column == self).unwrap();
+        write!(f, "{name}")
+    }
+}
+
+impl SortKey {
+    /// Names read best from A to Z, numbers and ages from the largest.
+    pub fn default_order(self) -> SortOrder {
+        match self {
+            SortKey::Name | SortKey::Path => SortOrder::Ascending,
+            _ => SortOrder::Descending,
+        }
+    }
+
+    // numeric value of a file, `None` if it is unknown
+    fn value(self, file: &File) -> Option<u64> {
+        match self {
+            SortKey::Loc => Some(file.loc as u64),
+            SortKey::Commits => file.commits.map(|x| x as u64),
+            SortKey::Churn => file.churn.map(|x| x as u64),
+            SortKey::Bytes => Some(file.bytes as u64),
+            // the earlier the last commit, the older the file
+            SortKey::Age => file.age.as_ref().map(|x| u64::MAX - x.last_commit),
+            SortKey::Todos => file.todos.as_ref().map(|x| x.len() as u64),
+            SortKey::Functions => file.symbols.as_ref().map(|x| x.functions.len() as u64),
+            SortKey::Name | SortKey::Path => None,
+        }
+    }
+}
+
+impl TableOptions {
+    /// Sorts the files by the chosen key, or by `default` if none was chosen. Files without a
+    /// value for the key, like untracked files for `commits`, always come last.
+    pub fn sort(&self, files: &mut [&File], default: SortKey) {
+        let key = self.sort_by.unwrap_or(default);
+        let order = self.order.unwrap_or(key.default_order());
+        files.sort_by(|a, b| {
+            let ordering = match key {
+                SortKey::Name => a.name.cmp(&b.name),
+                SortKey::Path => a.path.cmp(&b.path),
+                _ => return compare_values(key.value(a), key.value(b), order),
+            };
+            match order {
+                SortOrder::Ascending => ordering,
+                SortOrder::Descending => ordering.reverse(),
+            }
+        });
+    }
+
+    /// Sorts groups of files by the sum of the chosen key, by file type name for `name` and
+    /// `path`, and by the number of files if no key was chosen.
+    pub fn sort_groups(&self, groups: &mut [(&FileType, &Vec<File>)]) {
+        let Some(key) = self.sort_by else {
+            groups.sort_by_key(|(_, files)| Reverse(files.len()));
+            return;
+        };
+        let order = self.order.unwrap_or(key.default_order());
+        groups.sort_by(|(a_type, a_files), (b_type, b_files)| {
+            let ordering = match key {
+                SortKey::Name | SortKey::Path => a_type.to_string().cmp(&b_type.to_string()),
+                SortKey::Age => {
+                    let oldest = |files: &[File]| files.iter().filter_map(|x| key.value(x)).max();
+                    return compare_values(oldest(a_files), oldest(b_files), order);
+                }
+                _ => {
+                    let sum = |files: &[File]| files.iter().filter_map(|x| key.value(x)).sum();
+                    return compare_values(Some(sum(a_files)), Some(sum(b_files)), order);
+                }
+            };
+            match order {
+                SortOrder::Ascending => ordering,
+                SortOrder::Descending => ordering.reverse(),
+            }
+        });
+    }
+
+    /// Leaves out the groups beyond `num_groups` and returns how many were left out.
+    pub fn limit_groups<T>(&self, groups: &mut Vec<T>) -> usize {
+        let hidden = groups
+            .len()
+            .saturating_sub(self.num_groups.unwrap_or(usize::MAX));
+        groups.truncate(groups.len() - hidden);
+        hidden
+    }
+
+    /// The files with at least `min_loc` lines of code.
+    pub fn filter<'a>(&self, files: impl Iterator<Item = &'a File>) -> Vec<&'a File> {
+        files.filter(|x| x.loc >= self.min_loc).collect()
+    }
+
+    /// The chosen columns, or `default` if none were chosen.
+    pub fn columns(&self, default: Vec<Column>) -> Vec<Column> {
+        self.columns.clone().unwrap_or(default)
+    }
+
+    fn uses(&self, key: SortKey, column: Column) -> bool {
+        self.sort_by == Some(key) || self.columns.as_ref().is_some_and(|x| x.contains(&column))
+    }
+
+    pub fn needs_commits(&self) -> bool {
+        self.uses(SortKey::Commits, Column::Commits)
+    }
+
+    pub fn needs_churn(&self) -> bool {
+        self.uses(SortKey::Churn, Column::Churn)
+    }
+
+    pub fn needs_age(&self) -> bool {
+        [Column::Age, Column::LastCommit, Column::LineAge]
+            .into_iter()
+            .any(|x| self.uses(SortKey::Age, x))
+    }
+}
+
+fn compare_values(a: Option<u64>, b: Option<u64>, order: SortOrder) -> Ordering {
+    match (a, b, order) {
+        (Some(a), Some(b), SortOrder::Ascending) => a.cmp(&b),
+        (Some(a), Some(b), SortOrder::Descending) => b.cmp(&a),
+        (Some(_), None, _) => Ordering::Less,
+        (None, Some(_), _) => Ordering::Greater,
+        (None, None, _) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::file_from_content;
+
+    #[test]
+    fn test_sort() {
+        let mut a = file_from_content("src/b.rs", "fn a() {}\n");
+        a.commits = Some(4);
+        let mut b = file_from_content("src/a.rs", "fn b() {}\nfn c() {}\n");
+        b.commits = Some(9);
+        let c = file_from_content("untracked.rs", "fn d() {}\n");
+        let paths = |files: &[&File]| files.iter().map(|x| x.path.clone()).collect::<Vec<_>>();
+
+        let mut files = vec![&a, &b, &c];
+        TableOptions::default().sort(&mut files, SortKey::Loc);
+        assert_eq!(paths(&files), ["src/a.rs", "src/b.rs", "untracked.rs"]);
+
+        let options = TableOptions {
+            sort_by: Some(SortKey::Commits),
+            order: Some(SortOrder::Ascending),
+            ..Default::default()
+        };
+        options.sort(&mut files, SortKey::Loc);
+        assert_eq!(paths(&files), ["src/b.rs", "src/a.rs", "untracked.rs"]);
+        assert!(options.needs_commits() && !options.needs_churn());
+
+        let options = TableOptions {
+            sort_by: Some(SortKey::Name),
+            columns: Some(vec![Column::Name, Column::Age]),
+            min_loc: 2,
+            ..Default::default()
+        };
+        assert_eq!(paths(&options.filter(files.iter().copied())), ["src/a.rs"]);
+        options.sort(&mut files, SortKey::Loc);
+        assert_eq!(paths(&files), ["src/a.rs", "src/b.rs", "untracked.rs"]);
+        assert!(options.needs_age());
+
+        assert!("loc, bytes".split(',').all(|x| x.parse::<Column>().is_ok()));
+        assert!("size".parse::<Column>().is_err());
+        assert_eq!(
+            "line-age".parse::<Column>().unwrap().to_string(),
+            "line-age"
+        );
+        assert_eq!("todos".parse::<SortKey>().unwrap(), SortKey::Todos);
+    }
+}
@@ -0,0 +1,334 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    file::FileType,
+    syntax::{strip_source, syntax, SourceLine},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct FunctionInfo {
+    pub name: String,
+    /// 1-based line of the function signature
+    pub line: usize,
+    /// Number of lines from the signature to the end of the body
+    pub length: usize,
+    pub complexity: usize,
+}
+
+/// Cyclomatic complexity estimated by counting decision points, one plus the number of
+/// branches for the whole file and for every function found in it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Complexity {
+    pub total: usize,
+    pub functions: Vec<FunctionInfo>,
+}
+
+impl Complexity {
+    /// Complexity of every function, or of the whole file if no functions were found.
+    pub fn values(&self) -> Vec<usize> {
+        if self.functions.is_empty() {
+            return vec![self.total];
+        }
+        self.functions.iter().map(|x| x.complexity).collect()
+    }
+
+    pub fn maximum(&self) -> usize {
+        self.values().into_iter().max().unwrap_or(1)
+    }
+
+    pub fn average(&self) -> f64 {
+        average(&self.values())
+    }
+}
+
+pub fn average(values: &[usize]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<usize>() as f64 / values.len() as f64
+}
+
+struct Patterns {
+    decisions: Regex,
+    function: Regex,
+}
+
+fn patterns(file_type: FileType) -> Option<Patterns> {
+    let (decisions, function) = match file_type {
+        FileType::Rust => (
+            r"\belse\s+if\b|\b(if|else|match|for|while|loop)\b|&&|\|\|",
+            r"\bfn\s+(?<name>\w+)",
+        ),
+        FileType::Go => (
+            r"\belse\s+if\b|\b(if|else|switch|case|for|select)\b|&&|\|\|",
+            r"^\s*func\s*(\([^)]*\)\s*)?(?<name>\w+)",
+        ),
+        FileType::Python | FileType::Mojo => (
+            r"\b(if|elif|else|match|case|for|while|except|and|or)\b",
+            r"^\s*(async\s+)?(def|fn)\s+(?<name>\w+)",
+        ),
+        FileType::JavaScript | FileType::TypeScript => (
+            r"\belse\s+if\b|\b(if|else|case|for|while|catch)\b|&&|\|\||\?\?",
+            r"\bfunction\s*\*?\s*(?<name>\w+)|\b(const|let|var)\s+(?<arrow>\w+)\s*(:[^=]+)?=\s*(async\s+)?(\([^)]*\)|\w+)\s*(:[^=]+)?=>|^\s*((public|private|protected|static|async|get|set|override)\s+)*(?<method>\w+)\s*(<[^>]*>)?\([^)]*\)\s*(:[^{]+)?\{",
+        ),
+        FileType::Java
+        | FileType::C
+        | FileType::CHeader
+        | FileType::CPlusPlus
+        | FileType::CSharp
+        | FileType::Arduino => (
+            r"\belse\s+if\b|\b(if|else|case|for|while|catch)\b|&&|\|\|",
+            r"^\s*([\w:<>,\*&\[\]]+\s+)+[\*&]*(?<name>~?[\w:]+)\s*\(",
+        ),
+        _ => return None,
+    };
+
+    Some(Patterns {
+        decisions: Regex::new(decisions).unwrap(),
+        function: Regex::new(function).unwrap(),
+    })
+}
+
+const NOT_FUNCTIONS: [&str; 15] = [
+    "if", "else", "for", "while", "switch", "catch", "return", "new", "delete", "sizeof", "throw",
+    "case", "do", "function", "typeof",
+];
+
+fn function_name(patterns: &Patterns, code: &str) -> Option<String> {
+    let captures = patterns.function.captures(code)?;
+    let name = ["name", "arrow", "method"]
+        .iter()
+        .find_map(|x| captures.name(x))?
+        .as_str();
+
+    // calls like `return foo(x)` look like signatures in C-like languages
+    let first_word = code.split_whitespace().next().unwrap_or_default();
+    if NOT_FUNCTIONS.contains(&name) || NOT_FUNCTIONS.contains(&first_word) {
+        return None;
+    }
+
+    Some(name.to_string())
+}
+
+/// Estimates the complexity of a source file, `None` for file types without decision rules.
+pub fn complexity(content: &str, file_type: FileType) -> Option<Complexity> {
+    let syntax = syntax(file_type)?;
+    let patterns = patterns(file_type)?;
+    let lines = strip_source(content, &syntax);
+    let functions = find_functions(&lines, file_type, &patterns);
+
+    let decisions = lines
+        .iter()
+        .map(|x| patterns.decisions.find_iter(&x.code).count())
+        .sum::<usize>();
+
+    Some(Complexity {
+        total: 1 + decisions,
+        functions,
+    })
+}
+
+/// Functions with a body in lines stripped by [`strip_source`], ordered by their signature.
+pub fn functions(lines: &[SourceLine], file_type: FileType) -> Vec<FunctionInfo> {
+    match patterns(file_type) {
+        Some(patterns) => find_functions(lines, file_type, &patterns),
+        None => Vec::new(),
+    }
+}
+
+fn find_functions(
+    lines: &[SourceLine],
+    file_type: FileType,
+    patterns: &Patterns,
+) -> Vec<FunctionInfo> {
+    let mut functions = match file_type {
+        FileType::Python | FileType::Mojo => indented_functions(lines, patterns),
+        _ => braced_functions(lines, patterns),
+    };
+    functions.sort_by_key(|x| x.line);
+    functions
+}
+
+// functions whose body is the block following the signature, decisions are attributed
+// to the innermost function of the line
+fn braced_functions(lines: &[SourceLine], patterns: &Patterns) -> Vec<FunctionInfo> {
+    let mut functions = Vec::new();
+    // every open brace, with the function whose body it starts
+    let mut blocks: Vec<Option<usize>> = Vec::new();
+    let mut pending: Option<(String, usize)> = None;
+    let mut nesting = 0;
+
+    for (index, line) in lines.iter().enumerate() {
+        if let Some(name) = function_name(patterns, &line.code) {
+            pending = Some((name, index));
+            nesting = 0;
+        }
+
+        let mut owner = blocks.iter().rev().find_map(|x| *x);
+        for ch in line.code.chars() {
+            match ch {
+                '(' | '[' => nesting += 1,
+                ')' | ']' => nesting -= 1,
+                // declarations without a body, like trait methods or prototypes
+                ';' if nesting <= 0 => pending = None,
+                '{' => {
+                    let function = pending.take().map(|(name, start)| {
+                        functions.push(FunctionInfo {
+                            name,
+                            line: start + 1,
+                            length: 1,
+                            complexity: 1,
+                        });
+                        functions.len() - 1
+                    });
+                    if function.is_some() {
+                        owner = function;
+                    }
+                    blocks.push(function);
+                }
+                '}' => {
+                    if let Some(Some(function)) = blocks.pop() {
+                        let info = &mut functions[function];
+                        info.length = index + 2 - info.line;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(function) = owner {
+            functions[function].complexity += patterns.decisions.find_iter(&line.code).count();
+        }
+    }
+
+    functions
+}
+
+// functions whose body are the following lines with a deeper indentation
+fn indented_functions(lines: &[SourceLine], patterns: &Patterns) -> Vec<FunctionInfo> {
+    let mut functions: Vec<FunctionInfo> = Vec::new();
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut brackets = 0;
+
+    for (index, line) in lines.iter().enumerate() {
+        let code = line.code.trim_end();
+        if code.trim().is_empty() {
+            continue;
+        }
+
+        // continuation lines of a bracket spanning multiple lines keep the current function
+        if brackets <= 0 {
+            let indent = code.len() - code.trim_start().len();
+            while open.last().is_some_and(|(x, _)| *x >= indent) {
+                open.pop();
+            }
+            if let Some(name) = function_name(patterns, code) {
+                functions.push(FunctionInfo {
+                    name,
+                    line: index + 1,
+                    length: 1,
+                    complexity: 1,
+                });
+                open.push((indent, functions.len() - 1));
+            }
+        }
+
+        for ch in code.chars() {
+            match ch {
+                '(' | '[' | '{' => brackets += 1,
+                ')' | ']' | '}' => brackets -= 1,
+                _ => {}
+            }
+        }
+
+        for (_, function) in open.iter() {
+            let info = &mut functions[*function];
+            info.length = index + 2 - info.line;
+        }
+        if let Some((_, function)) = open.last() {
+            functions[*function].complexity += patterns.decisions.find_iter(code).count();
+        }
+    }
+
+    functions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(content: &str, file_type: FileType) -> Vec<(String, usize, usize, usize)> {
+        complexity(content, file_type)
+            .unwrap()
+            .functions
+            .into_iter()
+            .map(|x| (x.name, x.line, x.length, x.complexity))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_complexity() {
+        let content = r#"
+trait Shape {
+    fn area(&self) -> f64;
+}
+
+fn classify(x: i32, y: [u8; 2]) -> &'static str {
+    // if this were a comment it would not count
+    if x > 0 && y[0] > 0 {
+        "positive"
+    } else if x < 0 {
+        "negative || zero"
+    } else {
+        "zero"
+    }
+}
+
+fn main() { for i in 0..3 { println!("{i}") } }
+"#;
+        assert_eq!(
+            summary(content, FileType::Rust),
+            vec![
+                (String::from("classify"), 6, 10, 5),
+                (String::from("main"), 17, 1, 2),
+            ]
+        );
+
+        let file = complexity(content, FileType::Rust).unwrap();
+        assert_eq!(file.total, 6);
+        assert_eq!(file.maximum(), 5);
+        assert_eq!(file.average(), 3.5);
+        assert!(complexity(content, FileType::Markdown).is_none());
+    }
+
+    #[test]
+    fn test_c_like_complexity() {
+        let content = "int add(int a, int b);\n\nstatic int max(int a, int b)\n{\n    return a > b ? a : b;\n}\n\nint main(void) {\n    int x = max(1, 2);\n    while (x--) {\n        if (x == 1 || x == 3) return add(x, 1);\n    }\n    return 0;\n}\n";
+        assert_eq!(
+            summary(content, FileType::C),
+            vec![
+                (String::from("max"), 3, 4, 1),
+                (String::from("main"), 8, 7, 4),
+            ]
+        );
+
+        let content = "export const load = async (id: string) => {\n  try {\n    return await get(id) ?? null;\n  } catch (e) {\n    return null;\n  }\n};\nconst double = (x) => x * 2;\n";
+        assert_eq!(
+            summary(content, FileType::TypeScript),
+            vec![(String::from("load"), 1, 7, 3)]
+        );
+    }
+
+    #[test]
+    fn test_python_complexity() {
+        let content = "class A:\n    def run(self, items,\n    retries=3):\n        for x in items:\n            if x and not x.done:\n                x.start()\n\n        return None\n\ndef main():\n    pass\n";
+        assert_eq!(
+            summary(content, FileType::Python),
+            vec![
+                (String::from("run"), 2, 7, 4),
+                (String::from("main"), 10, 2, 1),
+            ]
+        );
+    }
+}
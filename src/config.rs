@@ -0,0 +1,102 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::{check::Rules, git::AuthorGrouping};
+
+pub const DEFAULT_CONFIG_FILE: &str = ".code-peek.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub authors: AuthorConfig,
+    pub check: Rules,
+    pub todos: TodoConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthorConfig {
+    /// Canonical author name mapped to the names and emails that should be counted as that author
+    pub aliases: HashMap<String, Vec<String>>,
+    /// Regular expressions matched against `Name <email>`, matching authors are left out
+    pub exclude: Vec<String>,
+    pub group_by: Option<AuthorGrouping>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TodoConfig {
+    /// Markers to look for in addition to TODO, FIXME, HACK and XXX
+    pub markers: Vec<String>,
+}
+
+/// The given config path, or `.code-peek.toml` in the analyzed directory if it exists.
+pub fn config_path(path: Option<&str>, dir: &str) -> Option<PathBuf> {
+    match path {
+        Some(path) => Some(Path::new(path).to_path_buf()),
+        None => {
+            let default_path = Path::new(dir).join(DEFAULT_CONFIG_FILE);
+            default_path.is_file().then_some(default_path)
+        }
+    }
+}
+
+pub fn load_config(path: Option<&str>, dir: &str) -> Result<Config> {
+    let Some(path) = config_path(path, dir) else {
+        return Ok(Config::default());
+    };
+
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("could not read config file {}", path.display()))?;
+
+    parse_config(&content).with_context(|| format!("invalid config file {}", path.display()))
+}
+
+fn parse_config(content: &str) -> Result<Config> {
+    Ok(toml::from_str(content)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = parse_config(
+            r#"
+            [authors]
+            exclude = ["dependabot"]
+            group_by = "domain"
+
+            [authors.aliases]
+            "Jane Doe" = ["jane", "Jane D.", "jane@example.com"]
+
+            [todos]
+            markers = ["PERF"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.authors.exclude, vec!["dependabot"]);
+        assert_eq!(config.authors.group_by, Some(AuthorGrouping::Domain));
+        assert_eq!(
+            config.authors.aliases.get("Jane Doe"),
+            Some(&vec![
+                String::from("jane"),
+                String::from("Jane D."),
+                String::from("jane@example.com")
+            ])
+        );
+
+        assert_eq!(config.todos.markers, vec!["PERF"]);
+
+        assert!(parse_config("").is_ok());
+        assert!(parse_config("[unknown]").is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    file::FileType,
+    symbols::find_symbols,
+    syntax::{strip_source, syntax},
+};
+
+/// Name of the counter used for supported file types, depending on the `tree-sitter` feature.
+#[cfg(feature = "tree-sitter")]
+pub const COUNTER: &str = "tree-sitter";
+#[cfg(not(feature = "tree-sitter"))]
+pub const COUNTER: &str = "regex";
+
+/// Lines split into code, comments and blanks, and the number of functions, classes and
+/// imports of a source file.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct SourceStats {
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+    pub functions: usize,
+    pub classes: usize,
+    pub imports: usize,
+}
+
+impl SourceStats {
+    pub fn add(&mut self, other: &SourceStats) {
+        self.code += other.code;
+        self.comments += other.comments;
+        self.blanks += other.blanks;
+        self.functions += other.functions;
+        self.classes += other.classes;
+        self.imports += other.imports;
+    }
+}
+
+/// Line classes of a source file, with code taking precedence over comments on the same line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    Code,
+    Comment,
+    Blank,
+}
+
+impl LineKind {
+    pub fn count(kinds: &[LineKind], stats: &mut SourceStats) {
+        for kind in kinds {
+            match kind {
+                LineKind::Code => stats.code += 1,
+                LineKind::Comment => stats.comments += 1,
+                LineKind::Blank => stats.blanks += 1,
+            }
+        }
+    }
+}
+
+/// Counts lines and symbols of a source file, using tree-sitter grammars if the feature is
+/// enabled and the regex counter for everything else. `None` for file types that are not
+/// source code. The path tells TypeScript and TSX apart.
+#[cfg_attr(not(feature = "tree-sitter"), allow(unused_variables))]
+pub fn count_source(path: &str, content: &str, file_type: FileType) -> Option<SourceStats> {
+    #[cfg(feature = "tree-sitter")]
+    if let Some(stats) = crate::parser::count_source(path, content, file_type) {
+        return Some(stats);
+    }
+
+    count_with_patterns(content, file_type)
+}
+
+fn import_pattern(file_type: FileType) -> Option<Regex> {
+    let imports = match file_type {
+        FileType::Rust => r"^\s*(pub(\([^)]*\))?\s+)?use\s",
+        FileType::Go => r#"^\s*import\s+([\w.]+\s+)?""#,
+        FileType::Python | FileType::Mojo => r"^\s*(import|from)\s+[\w.]+",
+        FileType::JavaScript | FileType::TypeScript => r#"^\s*import\s*[\w{*"']"#,
+        FileType::Java => r"^\s*import\s",
+        FileType::CSharp => r"^\s*using\s+[\w.=\s]+;",
+        FileType::C | FileType::CHeader | FileType::CPlusPlus | FileType::Arduino => {
+            r"^\s*#\s*include\b"
+        }
+        _ => return None,
+    };
+
+    Some(Regex::new(imports).unwrap())
+}
+
+// the fallback counter, working on the lines without comments and string contents
+fn count_with_patterns(content: &str, file_type: FileType) -> Option<SourceStats> {
+    let syntax = syntax(file_type)?;
+    let imports = import_pattern(file_type)?;
+    let lines = strip_source(content, &syntax);
+    let symbols = find_symbols(&lines, file_type)?;
+
+    let kinds = content
+        .lines()
+        .zip(lines.iter())
+        .map(|(line, source)| {
+            if line.trim().is_empty() {
+                LineKind::Blank
+            } else if source.code.trim().is_empty() && source.has_comment {
+                LineKind::Comment
+            } else {
+                LineKind::Code
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut stats = SourceStats {
+        functions: symbols.functions.len(),
+        classes: symbols.types,
+        ..Default::default()
+    };
+    LineKind::count(&kinds, &mut stats);
+
+    // the path of Go imports in parentheses is the only code on its line
+    let go_block_import = Regex::new(r#"^\s*([\w.]+\s+)?""\s*$"#).unwrap();
+    let mut in_import_block = false;
+    for line in lines.iter().map(|x| x.code.trim()) {
+        if in_import_block {
+            if line.starts_with(')') {
+                in_import_block = false;
+            } else if go_block_import.is_match(line) {
+                stats.imports += 1;
+            }
+            continue;
+        }
+        if file_type == FileType::Go && line.starts_with("import") && line.ends_with('(') {
+            in_import_block = true;
+        }
+
+        if imports.is_match(line) {
+            stats.imports += 1;
+        }
+    }
+
+    Some(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_with_patterns() {
+        let content = r#"use std::fs;
+use crate::file::{File, FileType};
+
+/// A parsed file
+pub struct Parsed {
+    content: String, // the content
+}
+
+/* not code:
+   fn hidden() {} */
+fn parse(path: &str) -> Parsed {
+    let content = "// not a comment";
+    Parsed { content: fs::read_to_string(path).unwrap() }
+}
+"#;
+        assert_eq!(
+            count_with_patterns(content, FileType::Rust),
+            Some(SourceStats {
+                code: 9,
+                comments: 3,
+                blanks: 2,
+                functions: 1,
+                classes: 1,
+                imports: 2,
+            })
+        );
+
+        let content = "package main\n\nimport (\n\t\"fmt\"\n\tstr \"strings\"\n)\n\ntype Point struct {\n\tX int\n}\n\nfunc main() {\n\tfmt.Println(str.ToUpper(\"a\"))\n}\n";
+        let stats = count_with_patterns(content, FileType::Go).unwrap();
+        assert_eq!((stats.functions, stats.classes, stats.imports), (1, 1, 2));
+
+        let content = "import os\nfrom typing import (\n    List,\n)\n\n\nclass A:\n    \"\"\"Docs\n\n    more docs\"\"\"\n    def run(self):  # go\n        pass\n";
+        let stats = count_with_patterns(content, FileType::Python).unwrap();
+        assert_eq!((stats.code, stats.comments, stats.blanks), (9, 0, 3));
+        assert_eq!((stats.functions, stats.classes, stats.imports), (1, 1, 2));
+
+        assert!(count_with_patterns("# Title", FileType::Markdown).is_none());
+    }
+}
@@ -0,0 +1,631 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    fs,
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use ignore::overrides::Override;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use term_table::{
+    row::Row,
+    table_cell::{Alignment, TableCell},
+    TableBuilder, TableStyle,
+};
+
+use crate::{
+    cli::DiffOptions,
+    columns::{Column, SortKey, TableOptions},
+    display::{add_more_row, format_delta},
+    file::{File, FileType},
+    revision::{get_blobs_at_revision, ObjectReader},
+};
+
+// revisions only tell the content of a file, not its history
+const DIFF_SORT_KEYS: [SortKey; 4] = [SortKey::Loc, SortKey::Bytes, SortKey::Name, SortKey::Path];
+const DIFF_COLUMNS: [Column; 5] = [
+    Column::Path,
+    Column::Name,
+    Column::Type,
+    Column::Loc,
+    Column::Change,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffFormat {
+    Table,
+    Json,
+    Markdown,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct FileDelta {
+    pub path: String,
+    pub file_type: FileType,
+    pub loc_before: usize,
+    pub loc_after: usize,
+    pub delta: i64,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct FileTypeDelta {
+    pub file_type: FileType,
+    pub files_before: usize,
+    pub files_after: usize,
+    pub loc_before: usize,
+    pub loc_after: usize,
+    pub delta: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffReport {
+    pub from: String,
+    pub to: String,
+    pub loc_before: usize,
+    pub loc_after: usize,
+    pub added: Vec<FileDelta>,
+    pub removed: Vec<FileDelta>,
+    pub changed: Vec<FileDelta>,
+    pub file_types: Vec<FileTypeDelta>,
+}
+
+impl DiffReport {
+    /// Added and changed files, sorted by the number of lines they gained.
+    pub fn largest_growth(&self) -> Vec<&FileDelta> {
+        let mut grown = self
+            .added
+            .iter()
+            .chain(self.changed.iter())
+            .filter(|x| x.delta > 0)
+            .collect::<Vec<_>>();
+        grown.sort_by(|a, b| b.delta.cmp(&a.delta).then_with(|| a.path.cmp(&b.path)));
+        grown
+    }
+}
+
+pub fn run_diff(
+    dir: &str,
+    overrides: &Override,
+    skip_lockfiles: &bool,
+    options: &DiffOptions,
+    table: &TableOptions,
+) -> Result<()> {
+    if let Some(key) = table.sort_by.filter(|x| !DIFF_SORT_KEYS.contains(x)) {
+        bail!("diff can not sort by {key}, expected loc, bytes, name or path");
+    }
+    let columns = table.columns(vec![Column::Path, Column::Change]);
+    if let Some(column) = columns.iter().find(|x| !DIFF_COLUMNS.contains(x)) {
+        bail!("diff has no {column} column, expected path, name, type, loc or change");
+    }
+
+    let mut reader = ObjectReader::new(dir)?;
+    let before = get_blobs_at_revision(&mut reader, dir, &options.from, overrides, skip_lockfiles)?;
+    let after = get_blobs_at_revision(&mut reader, dir, &options.to, overrides, skip_lockfiles)?;
+
+    let report = diff_files(&options.from, &options.to, before, after, table);
+
+    let output = match options.format {
+        DiffFormat::Table => render_table(&report, &columns, table),
+        DiffFormat::Json => render_json(&report, table.columns.as_deref())? + "\n",
+        DiffFormat::Markdown => render_markdown(&report, &columns, table),
+    };
+
+    match &options.output {
+        Some(path) => {
+            fs::write(path, output).with_context(|| format!("could not write to {path}"))?
+        }
+        None => print!("{output}"),
+    }
+
+    Ok(())
+}
+
+fn diff_files(
+    from: &str,
+    to: &str,
+    before: Vec<(String, File)>,
+    after: Vec<(String, File)>,
+    table: &TableOptions,
+) -> DiffReport {
+    let before_map: HashMap<&str, &(String, File)> =
+        before.iter().map(|x| (x.1.path.as_str(), x)).collect();
+    let after_map: HashMap<&str, &(String, File)> =
+        after.iter().map(|x| (x.1.path.as_str(), x)).collect();
+
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+    for (oid, file) in after.iter() {
+        match before_map.get(file.path.as_str()) {
+            None => added.push(file),
+            Some((old_oid, _)) if old_oid != oid => changed.push(file),
+            _ => {}
+        }
+    }
+    let mut removed = before
+        .iter()
+        .map(|(_, file)| file)
+        .filter(|file| !after_map.contains_key(file.path.as_str()))
+        .collect::<Vec<_>>();
+
+    let loc = |files: &HashMap<&str, &(String, File)>, file: &File| {
+        files.get(file.path.as_str()).map_or(0, |(_, x)| x.loc)
+    };
+    let [added, removed, changed] = [&mut added, &mut removed, &mut changed].map(|files| {
+        // by path, unless another key was chosen for the file tables
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        table.sort(files, SortKey::Path);
+        files
+            .iter()
+            .map(|file| file_delta(file, loc(&before_map, file), loc(&after_map, file)))
+            .collect::<Vec<_>>()
+    });
+
+    let mut file_types: BTreeMap<FileType, FileTypeDelta> = BTreeMap::new();
+    for (files, is_after) in [(&before, false), (&after, true)] {
+        for (_, file) in files.iter() {
+            let entry = file_types
+                .entry(file.file_type)
+                .or_insert_with(|| FileTypeDelta {
+                    file_type: file.file_type,
+                    files_before: 0,
+                    files_after: 0,
+                    loc_before: 0,
+                    loc_after: 0,
+                    delta: 0,
+                });
+            if is_after {
+                entry.files_after += 1;
+                entry.loc_after += file.loc;
+            } else {
+                entry.files_before += 1;
+                entry.loc_before += file.loc;
+            }
+        }
+    }
+    let mut file_types = file_types
+        .into_values()
+        .map(|mut x| {
+            x.delta = x.loc_after as i64 - x.loc_before as i64;
+            x
+        })
+        .collect::<Vec<_>>();
+    file_types.sort_by(|a, b| {
+        b.delta
+            .abs()
+            .cmp(&a.delta.abs())
+            .then(a.file_type.cmp(&b.file_type))
+    });
+
+    DiffReport {
+        from: from.to_string(),
+        to: to.to_string(),
+        loc_before: before.iter().map(|(_, x)| x.loc).sum(),
+        loc_after: after.iter().map(|(_, x)| x.loc).sum(),
+        added,
+        removed,
+        changed,
+        file_types,
+    }
+}
+
+fn file_delta(file: &File, loc_before: usize, loc_after: usize) -> FileDelta {
+    FileDelta {
+        path: file.path.clone(),
+        file_type: file.file_type,
+        loc_before,
+        loc_after,
+        delta: loc_after as i64 - loc_before as i64,
+    }
+}
+
+fn colored_delta(delta: i64) -> String {
+    match delta {
+        x if x > 0 => format_delta(x).green().to_string(),
+        x if x < 0 => format_delta(x).red().to_string(),
+        x => format_delta(x),
+    }
+}
+
+fn header(labels: &[&str]) -> Row<'static> {
+    Row::new(
+        labels
+            .iter()
+            .map(|x| TableCell::new_with_alignment(x.bright_blue().bold(), 1, Alignment::Center))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn column_label(column: Column) -> &'static str {
+    match column {
+        Column::Name => "Name",
+        Column::Type => "File type",
+        Column::Loc => "Lines of Code",
+        Column::Change => "Change",
+        _ => "File",
+    }
+}
+
+// cells that look the same in the table and the markdown output
+fn plain_cell(delta: &FileDelta, column: Column) -> String {
+    match column {
+        Column::Name => Path::new(&delta.path)
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        Column::Type => delta.file_type.to_string(),
+        _ => delta.path.clone(),
+    }
+}
+
+fn render_table(report: &DiffReport, columns: &[Column], table: &TableOptions) -> String {
+    let mut output = String::new();
+    writeln!(
+        output,
+        "\n{} {}..{}\n",
+        "Changes between".bright_blue().bold(),
+        report.from.bright_blue().bold(),
+        report.to.bright_blue().bold()
+    )
+    .unwrap();
+    writeln!(
+        output,
+        "{} {} added, {} removed, {} changed\n",
+        "Files:".bright_blue(),
+        report.added.len(),
+        report.removed.len(),
+        report.changed.len()
+    )
+    .unwrap();
+    writeln!(
+        output,
+        "{} {} -> {} ({})\n",
+        "Lines of code:".bright_blue(),
+        report.loc_before,
+        report.loc_after,
+        colored_delta(report.loc_after as i64 - report.loc_before as i64)
+    )
+    .unwrap();
+
+    let mut file_type_table = TableBuilder::new()
+        .has_top_boarder(true)
+        .style(TableStyle::thin())
+        .build();
+    file_type_table.add_row(header(&["File type", "Files", "Lines of Code", "Change"]));
+    let (file_types, hidden_file_types) = listed_file_types(report, table);
+    for x in file_types {
+        file_type_table.add_row(Row::new(vec![
+            x.file_type.to_string(),
+            format!("{} -> {}", x.files_before, x.files_after),
+            format!("{} -> {}", x.loc_before, x.loc_after),
+            colored_delta(x.delta),
+        ]));
+    }
+    add_more_row(&mut file_type_table, hidden_file_types, 4);
+    writeln!(output, "{}", file_type_table.render()).unwrap();
+
+    writeln!(output, "\n{}", "Files that grew the most".bright_blue()).unwrap();
+    let mut growth_table = TableBuilder::new()
+        .has_top_boarder(true)
+        .style(TableStyle::thin())
+        .build();
+    growth_table.add_row(header(&["File", "Before", "After", "Change"]));
+    let (grown, hidden_grown) = listed_files(report.largest_growth(), table);
+    for x in grown {
+        growth_table.add_row(Row::new(vec![
+            x.path.clone(),
+            x.loc_before.to_string(),
+            x.loc_after.to_string(),
+            colored_delta(x.delta),
+        ]));
+    }
+    add_more_row(&mut growth_table, hidden_grown, 4);
+    writeln!(output, "{}", growth_table.render()).unwrap();
+
+    for (title, deltas) in [
+        ("Added files", &report.added),
+        ("Removed files", &report.removed),
+        ("Changed files", &report.changed),
+    ] {
+        if deltas.is_empty() {
+            continue;
+        }
+        writeln!(output, "\n{}", title.bright_blue()).unwrap();
+        let mut delta_table = TableBuilder::new()
+            .has_top_boarder(true)
+            .style(TableStyle::thin())
+            .build();
+        delta_table.add_row(header(
+            &columns.iter().map(|x| column_label(*x)).collect::<Vec<_>>(),
+        ));
+        let (deltas, hidden) = listed_files(deltas.iter(), table);
+        for x in deltas {
+            delta_table.add_row(Row::new(
+                columns
+                    .iter()
+                    .map(|column| match column {
+                        Column::Path => x.path.clone(),
+                        Column::Loc => format!("{} -> {}", x.loc_before, x.loc_after),
+                        Column::Change => colored_delta(x.delta),
+                        _ => plain_cell(x, *column),
+                    })
+                    .collect::<Vec<_>>(),
+            ));
+        }
+        add_more_row(&mut delta_table, hidden, columns.len());
+        writeln!(output, "{}", delta_table.render()).unwrap();
+    }
+
+    output
+}
+
+/// The report as JSON, with only the chosen columns in the file lists.
+fn render_json(report: &DiffReport, columns: Option<&[Column]>) -> Result<String> {
+    let Some(columns) = columns else {
+        return Ok(serde_json::to_string_pretty(report)?);
+    };
+    let mut value = serde_json::to_value(report)?;
+    for (key, deltas) in [
+        ("added", &report.added),
+        ("removed", &report.removed),
+        ("changed", &report.changed),
+    ] {
+        value[key] = deltas
+            .iter()
+            .map(|x| {
+                let mut fields = Map::new();
+                for column in columns {
+                    match column {
+                        Column::Path => fields.insert("path".into(), x.path.clone().into()),
+                        Column::Type => fields.insert("file_type".into(), json!(x.file_type)),
+                        Column::Loc => {
+                            fields.insert("loc_before".into(), x.loc_before.into());
+                            fields.insert("loc_after".into(), x.loc_after.into())
+                        }
+                        Column::Change => fields.insert("delta".into(), x.delta.into()),
+                        _ => fields.insert(column.to_string(), plain_cell(x, *column).into()),
+                    };
+                }
+                Value::Object(fields)
+            })
+            .collect();
+    }
+    Ok(serde_json::to_string_pretty(&value)?)
+}
+
+fn render_markdown(report: &DiffReport, columns: &[Column], table: &TableOptions) -> String {
+    let mut output = String::new();
+    writeln!(
+        output,
+        "## Code changes between `{}` and `{}`\n",
+        report.from, report.to
+    )
+    .unwrap();
+    writeln!(
+        output,
+        "- Files: {} added, {} removed, {} changed",
+        report.added.len(),
+        report.removed.len(),
+        report.changed.len()
+    )
+    .unwrap();
+    writeln!(
+        output,
+        "- Lines of code: {} → {} ({})\n",
+        report.loc_before,
+        report.loc_after,
+        format_delta(report.loc_after as i64 - report.loc_before as i64)
+    )
+    .unwrap();
+
+    output
+        .push_str("| File type | Files | Lines of Code | Change |\n| --- | ---: | ---: | ---: |\n");
+    let (file_types, hidden_file_types) = listed_file_types(report, table);
+    for x in file_types {
+        writeln!(
+            output,
+            "| {} | {} → {} | {} → {} | {} |",
+            x.file_type,
+            x.files_before,
+            x.files_after,
+            x.loc_before,
+            x.loc_after,
+            format_delta(x.delta)
+        )
+        .unwrap();
+    }
+    markdown_more_row(&mut output, hidden_file_types, 4);
+
+    output.push_str(
+        "\n### Files that grew the most\n\n| File | Before | After | Change |\n| --- | ---: | ---: | ---: |\n",
+    );
+    let (grown, hidden_grown) = listed_files(report.largest_growth(), table);
+    for x in grown {
+        writeln!(
+            output,
+            "| `{}` | {} | {} | {} |",
+            x.path,
+            x.loc_before,
+            x.loc_after,
+            format_delta(x.delta)
+        )
+        .unwrap();
+    }
+    markdown_more_row(&mut output, hidden_grown, 4);
+
+    for (title, deltas) in [
+        ("Added files", &report.added),
+        ("Removed files", &report.removed),
+        ("Changed files", &report.changed),
+    ] {
+        if deltas.is_empty() {
+            continue;
+        }
+        let alignments = columns.iter().map(|x| match x {
+            Column::Loc | Column::Change => "---:",
+            _ => "---",
+        });
+        writeln!(
+            output,
+            "\n### {title}\n\n| {} |\n| {} |",
+            columns
+                .iter()
+                .map(|x| column_label(*x))
+                .collect::<Vec<_>>()
+                .join(" | "),
+            alignments.collect::<Vec<_>>().join(" | ")
+        )
+        .unwrap();
+        let (deltas, hidden) = listed_files(deltas.iter(), table);
+        for x in deltas {
+            let cells = columns.iter().map(|column| match column {
+                Column::Path => format!("`{}`", x.path),
+                Column::Loc => format!("{} → {}", x.loc_before, x.loc_after),
+                Column::Change => format_delta(x.delta),
+                _ => plain_cell(x, *column),
+            });
+            writeln!(output, "| {} |", cells.collect::<Vec<_>>().join(" | ")).unwrap();
+        }
+        markdown_more_row(&mut output, hidden, columns.len());
+    }
+
+    output
+}
+
+// the files with at least `min_loc` lines before or after, up to `num_files` of them, and how
+// many were left out
+fn listed_files<'a>(
+    deltas: impl IntoIterator<Item = &'a FileDelta>,
+    table: &TableOptions,
+) -> (Vec<&'a FileDelta>, usize) {
+    let mut deltas = deltas
+        .into_iter()
+        .filter(|x| x.loc_before.max(x.loc_after) >= table.min_loc)
+        .collect::<Vec<_>>();
+    let hidden = deltas.len().saturating_sub(table.num_files);
+    deltas.truncate(table.num_files);
+    (deltas, hidden)
+}
+
+// the changed file types with at least `min_loc` lines before or after, up to `num_groups` of
+// them, and how many were left out
+fn listed_file_types<'a>(
+    report: &'a DiffReport,
+    table: &TableOptions,
+) -> (Vec<&'a FileTypeDelta>, usize) {
+    let mut file_types = report
+        .file_types
+        .iter()
+        .filter(|x| x.delta != 0 && x.loc_before.max(x.loc_after) >= table.min_loc)
+        .collect::<Vec<_>>();
+    let hidden = table.limit_groups(&mut file_types);
+    (file_types, hidden)
+}
+
+fn markdown_more_row(output: &mut String, more: usize, columns: usize) {
+    if more > 0 {
+        writeln!(
+            output,
+            "| ... and {more} more |{}",
+            " |".repeat(columns - 1)
+        )
+        .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::file_from_content;
+
+    fn blob(oid: &str, path: &str, loc: usize) -> (String, File) {
+        (oid.to_string(), file_from_content(path, &"x\n".repeat(loc)))
+    }
+
+    #[test]
+    fn test_diff_files() {
+        let before = vec![
+            blob("a", "src/main.rs", 10),
+            blob("b", "src/lib.rs", 20),
+            blob("c", "index.js", 5),
+        ];
+        let after = vec![
+            blob("a", "src/main.rs", 10),
+            blob("d", "src/lib.rs", 50),
+            blob("e", "index.ts", 8),
+        ];
+
+        let report = diff_files("v1", "v2", before, after, &TableOptions::default());
+
+        assert_eq!(report.loc_before, 35);
+        assert_eq!(report.loc_after, 68);
+        assert_eq!(
+            report.added,
+            vec![FileDelta {
+                path: String::from("index.ts"),
+                file_type: FileType::TypeScript,
+                loc_before: 0,
+                loc_after: 8,
+                delta: 8,
+            }]
+        );
+        assert_eq!(report.removed.len(), 1);
+        assert_eq!(report.removed[0].delta, -5);
+        assert_eq!(report.changed.len(), 1);
+        assert_eq!(report.changed[0].path, "src/lib.rs");
+        assert_eq!(
+            report
+                .largest_growth()
+                .iter()
+                .map(|x| x.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["src/lib.rs", "index.ts"]
+        );
+        assert_eq!(
+            report.file_types[0],
+            FileTypeDelta {
+                file_type: FileType::Rust,
+                files_before: 2,
+                files_after: 2,
+                loc_before: 30,
+                loc_after: 60,
+                delta: 30,
+            }
+        );
+
+        let options = TableOptions {
+            sort_by: Some(SortKey::Loc),
+            columns: Some(vec![Column::Name, Column::Change]),
+            num_files: 1,
+            ..Default::default()
+        };
+        let report = diff_files(
+            "v1",
+            "v2",
+            vec![blob("a", "a.rs", 1)],
+            vec![
+                blob("b", "a.rs", 2),
+                blob("c", "b.rs", 9),
+                blob("d", "c.rs", 4),
+            ],
+            &options,
+        );
+        assert_eq!(
+            report
+                .added
+                .iter()
+                .map(|x| x.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["b.rs", "c.rs"]
+        );
+        let json = render_json(&report, options.columns.as_deref()).unwrap();
+        let value = serde_json::from_str::<Value>(&json).unwrap();
+        assert_eq!(value["changed"], json!([{ "name": "a.rs", "delta": 1 }]));
+        assert!(
+            render_markdown(&report, &[Column::Name, Column::Change], &options)
+                .contains("| Name | Change |\n| --- | ---: |\n| b.rs | +9 |\n| ... and 1 more | |")
+        );
+    }
+}
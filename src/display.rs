@@ -1,5 +1,10 @@
-use colored::Colorize;
-use std::collections::HashMap;
+use colored::{Color, Colorize};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use term_table::{
     row::Row,
     table_cell::{Alignment, TableCell},
@@ -7,40 +12,84 @@ use term_table::{
 };
 
 use crate::{
+    baseline::Baseline,
     cli::DisplayOptions,
-    file::{File, FileType},
-    git::get_git_authors,
+    columns::{Column, SortKey, TableOptions},
+    complexity::average,
+    counter::{SourceStats, COUNTER},
+    file::{identical_files, unique_files, wasted_loc, File, FileType},
+    git::{get_git_authors, AuthorGrouping, AuthorOptions, AGE_BUCKETS},
+    submodules::SubmoduleFiles,
+    syntax::syntax,
+    testing::{is_test_file, test_module_files},
+    todos::DEFAULT_MARKERS,
 };
 
 pub fn display_info(
-    files: &Vec<File>,
+    files: &[File],
     dir: &str,
-    options: DisplayOptions,
+    options: &DisplayOptions,
     total_commits: Option<usize>,
     num: usize,
+    author_options: &AuthorOptions,
+    baseline: Option<&Baseline>,
 ) {
     println!(
         "\n{} {}\n",
         "Summary for project".bright_blue().bold(),
         dir.bright_blue().bold()
     );
-    println!(
-        "{} {}\n",
-        "Total number of files:".bright_blue(),
-        files.len()
-    );
-    println!(
-        "{} {}\n",
-        "Total lines of code:".bright_blue(),
-        files.iter().map(|x| x.loc).sum::<usize>()
-    );
+    // the identical files report still needs every copy
+    let all_files = files;
+    let unique;
+    let files = if options.unique {
+        unique = unique_files(files);
+        println!(
+            "{} {}\n",
+            "Identical copies counted once:".bright_blue(),
+            all_files.len() - unique.len()
+        );
+        unique.as_slice()
+    } else {
+        files
+    };
+    let total_loc = files.iter().map(|x| x.loc).sum::<usize>();
+    match baseline {
+        Some(baseline) => {
+            println!(
+                "{} {} {}\n",
+                "Total number of files:".bright_blue(),
+                files.len(),
+                format_arrow(files.len() as i64 - baseline.total_files() as i64)
+            );
+            println!(
+                "{} {} {}\n",
+                "Total lines of code:".bright_blue(),
+                total_loc,
+                format_arrow(total_loc as i64 - baseline.total_loc() as i64)
+            );
+            println!(
+                "{} {}\n",
+                "Files removed since the baseline:".bright_blue(),
+                baseline.removed_files(files)
+            );
+        }
+        None => {
+            println!(
+                "{} {}\n",
+                "Total number of files:".bright_blue(),
+                files.len()
+            );
+            println!("{} {}\n", "Total lines of code:".bright_blue(), total_loc);
+        }
+    }
 
     let mut grouped_files: HashMap<FileType, Vec<File>> = HashMap::new();
 
     for file in files.iter() {
         grouped_files
             .entry(file.file_type)
-            .or_insert_with(|| Vec::new())
+            .or_default()
             .push(file.to_owned());
     }
     println!(
@@ -50,17 +99,62 @@ pub fn display_info(
     );
 
     if options.group || options.all {
-        grouped_info(&grouped_files, options.git || options.all);
+        grouped_info(&grouped_files, options, baseline);
     } else {
-        simple_info(files, num)
+        simple_info(files, options, baseline)
     }
 
-    if options.git || options.all {
-        display_git_info(files, num, dir, total_commits.unwrap_or(0))
+    if options.breakdown {
+        display_source_info(&grouped_files);
+    }
+
+    if options.symbols {
+        display_symbol_info(files, &grouped_files, &options.table);
+    }
+
+    if options.tests {
+        display_test_info(files, &grouped_files, &options.table);
+    }
+
+    if options.todos {
+        display_todo_info(files, &grouped_files, &options.table);
+    }
+
+    if options.identical {
+        display_identical_info(all_files, &options.table);
+    }
+
+    if options.bytes {
+        display_size_info(files, &options.table);
+    }
+
+    if options.age {
+        display_age_info(files, &grouped_files, &options.table);
+    }
+
+    // directories outside of git and archives have no history to show
+    if (options.git || options.all) && total_commits.is_some() {
+        display_git_info(
+            files,
+            num,
+            dir,
+            options.rev.as_deref(),
+            total_commits.unwrap_or(0),
+            author_options,
+            &options.table,
+        )
     }
 }
 
-fn display_git_info(files: &Vec<File>, num: usize, dir: &str, total_commits: usize) {
+fn display_git_info(
+    files: &[File],
+    num: usize,
+    dir: &str,
+    rev: Option<&str>,
+    total_commits: usize,
+    author_options: &AuthorOptions,
+    table_options: &TableOptions,
+) {
     println!("\n===================================\n");
     println!(
         "\n{}",
@@ -69,7 +163,12 @@ fn display_git_info(files: &Vec<File>, num: usize, dir: &str, total_commits: usi
 
     println!("{} {total_commits}\n", "Total number of commits:".yellow());
 
-    let authors = get_git_authors(dir, num).unwrap_or(vec![]);
+    let authors = get_git_authors(dir, rev, num, author_options).unwrap_or_default();
+    let author_header = match author_options.grouping {
+        AuthorGrouping::Name => "Author",
+        AuthorGrouping::Email => "Author email",
+        AuthorGrouping::Domain => "Email domain",
+    };
 
     if !authors.is_empty() {
         println!("-----------------------------------\n");
@@ -81,7 +180,7 @@ fn display_git_info(files: &Vec<File>, num: usize, dir: &str, total_commits: usi
             .build();
         author_table.add_row(Row::new(vec![
             TableCell::new_with_alignment(
-                "Author".to_string().yellow().bold(),
+                author_header.to_string().yellow().bold(),
                 1,
                 Alignment::Center,
             ),
@@ -102,47 +201,782 @@ fn display_git_info(files: &Vec<File>, num: usize, dir: &str, total_commits: usi
     }
     println!("\n-----------------------------------\n");
     println!("{}", "Most changed files based on commits".yellow());
-    let mut sorted_files: Vec<File> = files.clone().to_vec();
-    sorted_files.sort_by(|a, b| b.commits.unwrap_or(1).cmp(&a.commits.unwrap_or(1)));
+    let mut sorted_files = table_options.filter(files.iter());
+    table_options.sort(&mut sorted_files, SortKey::Commits);
+
+    let columns = table_options.columns(vec![Column::Path, Column::Commits]);
+    let commits_table = file_table(
+        &sorted_files,
+        table_options.num_files,
+        &columns,
+        "File",
+        Color::Yellow,
+        None,
+    );
+    println!("{}", commits_table.render())
+}
 
-    let largest_files = sorted_files.into_iter().take(num).collect::<Vec<_>>();
-    let mut commits_table = TableBuilder::new()
+fn display_source_info(grouped_files: &HashMap<FileType, Vec<File>>) {
+    println!("\n===================================\n");
+    println!(
+        "{} {}\n",
+        "Code, comments and symbols per file type, counted with"
+            .magenta()
+            .bold(),
+        COUNTER.magenta().bold()
+    );
+
+    let mut totals = grouped_files
+        .iter()
+        .filter_map(|(file_type, files)| {
+            let mut total = SourceStats::default();
+            for stats in files.iter().filter_map(|x| x.source.as_ref()) {
+                total.add(stats);
+            }
+            files
+                .iter()
+                .any(|x| x.source.is_some())
+                .then_some((file_type, total))
+        })
+        .collect::<Vec<_>>();
+    if totals.is_empty() {
+        println!("No source files found");
+        return;
+    }
+    totals.sort_by_key(|(_, total)| Reverse(total.code));
+
+    let mut table = TableBuilder::new()
+        .has_top_boarder(true)
+        .style(TableStyle::thin())
+        .build();
+    let header = [
+        "File type",
+        "Code",
+        "Comments",
+        "Blank",
+        "Comment ratio",
+        "Functions",
+        "Classes",
+        "Imports",
+    ];
+    table.add_row(Row::new(header.iter().map(|label| {
+        TableCell::new_with_alignment(label.to_string().magenta().bold(), 1, Alignment::Center)
+    })));
+    for (file_type, total) in totals {
+        table.add_row(Row::new(vec![
+            file_type.to_string(),
+            total.code.to_string(),
+            total.comments.to_string(),
+            total.blanks.to_string(),
+            format!(
+                "{:.1}%",
+                percentage(total.comments, total.code + total.comments)
+            ),
+            total.functions.to_string(),
+            total.classes.to_string(),
+            total.imports.to_string(),
+        ]));
+    }
+    println!("{}", table.render());
+}
+
+fn display_symbol_info(
+    files: &[File],
+    grouped_files: &HashMap<FileType, Vec<File>>,
+    options: &TableOptions,
+) {
+    println!("\n===================================\n");
+    println!("{}\n", "Symbols per file type".magenta().bold());
+
+    let mut sorted_entries = grouped_files
+        .iter()
+        .filter(|(_, files)| files.iter().any(|x| x.symbols.is_some()))
+        .collect::<Vec<_>>();
+    if sorted_entries.is_empty() {
+        println!("No source files found");
+        return;
+    }
+    sorted_entries
+        .retain(|(_, files)| files.iter().map(|x| x.loc).sum::<usize>() >= options.min_loc);
+    sorted_entries.sort_by_key(|(_, files)| {
+        Reverse(
+            files
+                .iter()
+                .filter_map(|x| x.symbols.as_ref())
+                .map(|x| x.functions.len())
+                .sum::<usize>(),
+        )
+    });
+    let hidden_groups = options.limit_groups(&mut sorted_entries);
+
+    let mut symbol_table = TableBuilder::new()
+        .has_top_boarder(true)
+        .style(TableStyle::thin())
+        .build();
+    let header = [
+        "File type",
+        "Functions",
+        "Structs/Classes",
+        "Traits/Interfaces",
+        "Tests",
+    ];
+    symbol_table.add_row(Row::new(header.iter().map(|label| {
+        TableCell::new_with_alignment(label.to_string().magenta().bold(), 1, Alignment::Center)
+    })));
+    for (file_type, files) in sorted_entries {
+        let symbols = files
+            .iter()
+            .filter_map(|x| x.symbols.as_ref())
+            .collect::<Vec<_>>();
+        symbol_table.add_row(Row::new(vec![
+            file_type.to_string(),
+            symbols
+                .iter()
+                .map(|x| x.functions.len())
+                .sum::<usize>()
+                .to_string(),
+            symbols.iter().map(|x| x.types).sum::<usize>().to_string(),
+            symbols.iter().map(|x| x.traits).sum::<usize>().to_string(),
+            symbols.iter().map(|x| x.tests).sum::<usize>().to_string(),
+        ]));
+    }
+    add_more_row(&mut symbol_table, hidden_groups, 5);
+    println!("{}", symbol_table.render());
+
+    println!("\n{}", "Symbols per file".magenta());
+    let mut symbol_files = options.filter(files.iter().filter(|x| x.symbols.is_some()));
+    options.sort(&mut symbol_files, SortKey::Functions);
+    let columns = options.columns(vec![
+        Column::Path,
+        Column::Functions,
+        Column::Types,
+        Column::Traits,
+        Column::Tests,
+    ]);
+    let table = file_table(
+        &symbol_files,
+        options.num_files,
+        &columns,
+        "File",
+        Color::Magenta,
+        None,
+    );
+    println!("{}", table.render());
+
+    let mut functions = options
+        .filter(files.iter())
+        .into_iter()
+        .filter_map(|file| file.symbols.as_ref().map(|x| (file, x)))
+        .flat_map(|(file, symbols)| symbols.functions.iter().map(move |x| (file, x)))
+        .collect::<Vec<_>>();
+    if functions.is_empty() {
+        return;
+    }
+    functions.sort_by_key(|(_, function)| Reverse(function.length));
+
+    println!("\n{}", "Largest functions".magenta());
+    let mut function_table = TableBuilder::new()
         .has_top_boarder(true)
         .style(TableStyle::thin())
         .build();
-    commits_table.add_row(Row::new(vec![
-        TableCell::new_with_alignment("File".to_string().yellow().bold(), 1, Alignment::Center),
+    function_table.add_row(Row::new(["Function", "Location", "Lines"].iter().map(
+        |label| {
+            TableCell::new_with_alignment(label.to_string().magenta().bold(), 1, Alignment::Center)
+        },
+    )));
+    for (file, function) in functions.iter().take(options.num_files) {
+        function_table.add_row(Row::new(vec![
+            function.name.clone(),
+            format!("{}:{}", file.path, function.line),
+            function.length.to_string(),
+        ]));
+    }
+    add_more_row(
+        &mut function_table,
+        functions.len().saturating_sub(options.num_files),
+        3,
+    );
+    println!("{}", function_table.render());
+}
+
+fn display_test_info(
+    files: &[File],
+    grouped_files: &HashMap<FileType, Vec<File>>,
+    options: &TableOptions,
+) {
+    println!("\n===================================\n");
+    println!("{}\n", "Test and production code".green().bold());
+
+    // source files count even without tests, data and config files only if they are test data
+    let is_code = |file: &File| syntax(file.file_type).is_some() || file.test_loc.unwrap_or(0) > 0;
+    let test_modules = test_module_files(files);
+
+    let file_types = grouped_files
+        .iter()
+        .map(|(file_type, files)| {
+            let files = files.iter().filter(|x| is_code(x)).collect::<Vec<_>>();
+            (file_type.to_string(), files)
+        })
+        .filter(|(_, files)| !files.is_empty())
+        .collect::<Vec<_>>();
+    println!("{}", "Test to code ratio per file type".green());
+    println!(
+        "{}",
+        test_table("File type", file_types, &test_modules, options).render()
+    );
+
+    let mut directories: HashMap<String, Vec<&File>> = HashMap::new();
+    for file in files.iter().filter(|x| is_code(x)) {
+        let directory = Path::new(&file.path)
+            .parent()
+            .map(|x| x.to_string_lossy().to_string())
+            .filter(|x| !x.is_empty())
+            .unwrap_or_else(|| ".".to_string());
+        directories.entry(directory).or_default().push(file);
+    }
+    println!("\n{}", "Test to code ratio per directory".green());
+    println!(
+        "{}",
+        test_table(
+            "Directory",
+            directories.into_iter().collect(),
+            &test_modules,
+            options
+        )
+        .render()
+    );
+}
+
+// one row per group with its production and test lines, sorted by the total lines, files
+// declared as test modules by another file are test code as a whole
+fn test_table<'a>(
+    label: &str,
+    mut groups: Vec<(String, Vec<&File>)>,
+    test_modules: &HashSet<&str>,
+    options: &TableOptions,
+) -> Table<'a> {
+    let is_test_module = |file: &File| test_modules.contains(file.path.as_str());
+    groups.retain(|(_, files)| files.iter().map(|x| x.loc).sum::<usize>() >= options.min_loc);
+    groups.sort_by_key(|(name, files)| {
+        (
+            Reverse(files.iter().map(|x| x.loc).sum::<usize>()),
+            name.clone(),
+        )
+    });
+    let hidden_groups = options.limit_groups(&mut groups);
+
+    let mut table = TableBuilder::new()
+        .has_top_boarder(true)
+        .style(TableStyle::thin())
+        .build();
+    let header = [
+        label,
+        "Production code",
+        "Test code",
+        "Test files",
+        "Test to code ratio",
+    ];
+    table.add_row(Row::new(header.iter().map(|label| {
+        TableCell::new_with_alignment(label.to_string().green().bold(), 1, Alignment::Center)
+    })));
+    for (name, files) in groups {
+        let loc = files.iter().map(|x| x.loc).sum::<usize>();
+        let test_loc = files
+            .iter()
+            .map(|x| {
+                if is_test_module(x) {
+                    x.loc
+                } else {
+                    x.test_loc.unwrap_or(0)
+                }
+            })
+            .sum::<usize>();
+        let production_loc = loc.saturating_sub(test_loc);
+        let test_files = files
+            .iter()
+            .filter(|x| is_test_file(&x.path, x.file_type) || is_test_module(x))
+            .count();
+        let ratio = match production_loc {
+            0 => "-".to_string(),
+            _ => format!("{:.2}", test_loc as f64 / production_loc as f64),
+        };
+        table.add_row(Row::new(vec![
+            name,
+            production_loc.to_string(),
+            test_loc.to_string(),
+            test_files.to_string(),
+            ratio,
+        ]));
+    }
+    add_more_row(&mut table, hidden_groups, 5);
+    table
+}
+
+fn display_todo_info(
+    files: &[File],
+    grouped_files: &HashMap<FileType, Vec<File>>,
+    options: &TableOptions,
+) {
+    println!("\n===================================\n");
+    println!("{}\n", "Todo markers in comments".yellow().bold());
+
+    let todos = files
+        .iter()
+        .filter_map(|file| file.todos.as_ref().map(|x| (file, x)))
+        .flat_map(|(file, todos)| todos.iter().map(move |x| (file, x)))
+        .collect::<Vec<_>>();
+    if todos.is_empty() {
+        println!("No markers found");
+        return;
+    }
+
+    // default markers in their usual order, custom ones after them
+    let mut markers = DEFAULT_MARKERS
+        .iter()
+        .map(|x| x.to_string())
+        .filter(|marker| todos.iter().any(|(_, x)| x.marker == *marker))
+        .collect::<Vec<_>>();
+    let mut custom_markers = todos
+        .iter()
+        .map(|(_, x)| x.marker.clone())
+        .filter(|x| !DEFAULT_MARKERS.contains(&x.as_str()))
+        .collect::<Vec<_>>();
+    custom_markers.sort();
+    custom_markers.dedup();
+    markers.extend(custom_markers);
+
+    let marker_count = |files: &[&File], marker: Option<&str>| {
+        files
+            .iter()
+            .filter_map(|x| x.todos.as_ref())
+            .flatten()
+            .filter(|x| marker.is_none_or(|marker| x.marker == marker))
+            .count()
+    };
+
+    let mut file_type_table = TableBuilder::new()
+        .has_top_boarder(true)
+        .style(TableStyle::thin())
+        .build();
+    let mut header = vec![TableCell::new_with_alignment(
+        "File type".to_string().yellow().bold(),
+        1,
+        Alignment::Center,
+    )];
+    for label in markers.iter().map(|x| x.as_str()).chain(["Total"]) {
+        header.push(TableCell::new_with_alignment(
+            label.to_string().yellow().bold(),
+            1,
+            Alignment::Center,
+        ));
+    }
+    file_type_table.add_row(Row::new(header));
+
+    let mut sorted_entries = grouped_files
+        .iter()
+        .map(|(file_type, files)| (file_type, files.iter().collect::<Vec<_>>()))
+        .filter(|(_, files)| marker_count(files, None) > 0)
+        .filter(|(_, files)| files.iter().map(|x| x.loc).sum::<usize>() >= options.min_loc)
+        .collect::<Vec<_>>();
+    sorted_entries.sort_by_key(|(_, files)| Reverse(marker_count(files, None)));
+    let hidden_groups = options.limit_groups(&mut sorted_entries);
+    for (file_type, files) in sorted_entries {
+        let mut row = vec![file_type.to_string()];
+        for marker in markers.iter() {
+            row.push(marker_count(&files, Some(marker)).to_string());
+        }
+        row.push(marker_count(&files, None).to_string());
+        file_type_table.add_row(Row::new(row));
+    }
+    add_more_row(&mut file_type_table, hidden_groups, markers.len() + 2);
+    println!("{}", file_type_table.render());
+
+    println!("\n{}", "Files with the most markers".yellow());
+    let mut sorted_files = options.filter(
+        files
+            .iter()
+            .filter(|x| x.todos.as_ref().is_some_and(|x| !x.is_empty())),
+    );
+    options.sort(&mut sorted_files, SortKey::Todos);
+    let columns = options.columns(vec![Column::Path, Column::Todos]);
+    let table = file_table(
+        &sorted_files,
+        options.num_files,
+        &columns,
+        "File",
+        Color::Yellow,
+        None,
+    );
+    println!("{}", table.render());
+
+    // with blame info the oldest markers are the most interesting ones
+    let blamed = todos.iter().any(|(_, x)| x.blame.is_some());
+    let mut occurrences = todos;
+    occurrences.retain(|(file, _)| file.loc >= options.min_loc);
+    if blamed {
+        occurrences
+            .sort_by_key(|(_, x)| x.blame.as_ref().map(|(_, time)| *time).unwrap_or(u64::MAX));
+        println!("\n{}", "Oldest markers".yellow());
+    } else {
+        println!("\n{}", "Markers".yellow());
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or(0);
+    let mut occurrence_table = TableBuilder::new()
+        .has_top_boarder(true)
+        .style(TableStyle::thin())
+        .build();
+    let mut header = vec!["Location", "Marker", "Text"];
+    if blamed {
+        header.extend(["Author", "Age"]);
+    }
+    occurrence_table.add_row(Row::new(header.iter().map(|label| {
+        TableCell::new_with_alignment(label.to_string().yellow().bold(), 1, Alignment::Center)
+    })));
+    let hidden_occurrences = occurrences.len().saturating_sub(options.num_files);
+    for (file, todo) in occurrences.into_iter().take(options.num_files) {
+        let mut row = vec![
+            format!("{}:{}", file.path, todo.line),
+            todo.marker.clone(),
+            todo.text.clone(),
+        ];
+        if blamed {
+            match &todo.blame {
+                Some((author, time)) => {
+                    row.push(author.clone());
+                    row.push(format_age(now.saturating_sub(*time) / 86_400));
+                }
+                None => row.extend(["-".to_string(), "-".to_string()]),
+            }
+        }
+        occurrence_table.add_row(Row::new(row));
+    }
+    add_more_row(&mut occurrence_table, hidden_occurrences, header.len());
+    println!("{}", occurrence_table.render());
+}
+
+fn display_identical_info(files: &[File], options: &TableOptions) {
+    println!("\n===================================\n");
+    println!("{}\n", "Identical files".magenta().bold());
+
+    let groups = identical_files(files);
+    if groups.is_empty() {
+        println!("No identical files found");
+        return;
+    }
+    println!(
+        "{} {}\n",
+        "Groups of identical files:".magenta(),
+        groups.len()
+    );
+    println!(
+        "{} {}\n",
+        "Lines of code in copies:".magenta(),
+        groups.iter().map(|x| wasted_loc(x)).sum::<usize>()
+    );
+
+    let mut table = TableBuilder::new()
+        .has_top_boarder(true)
+        .style(TableStyle::thin())
+        .build();
+    table.add_row(Row::new(
+        ["Copies", "Lines of Code", "Wasted lines", "Files"].map(|x| {
+            TableCell::new_with_alignment(x.to_string().magenta().bold(), 1, Alignment::Center)
+        }),
+    ));
+    let listed = groups
+        .iter()
+        .filter(|x| x[0].loc >= options.min_loc)
+        .collect::<Vec<_>>();
+    for group in listed.iter().take(options.num_files) {
+        table.add_row(Row::new(vec![
+            group.len().to_string(),
+            group[0].loc.to_string(),
+            wasted_loc(group).to_string(),
+            group
+                .iter()
+                .map(|x| x.path.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ]));
+    }
+    add_more_row(
+        &mut table,
+        listed.len().saturating_sub(options.num_files),
+        4,
+    );
+    println!("{}", table.render());
+}
+
+fn display_size_info(files: &[File], options: &TableOptions) {
+    println!("\n===================================\n");
+    println!("{}\n", "Largest files by bytes".green().bold());
+
+    let total_bytes = files.iter().map(|x| x.bytes).sum::<usize>();
+    println!("{} {}\n", "Total size:".green(), format_bytes(total_bytes));
+
+    let mut sorted_files = options.filter(files.iter());
+    options.sort(&mut sorted_files, SortKey::Bytes);
+    let columns = options.columns(vec![
+        Column::Path,
+        Column::Bytes,
+        Column::Loc,
+        Column::LongestLine,
+        Column::LineLength,
+    ]);
+    let table = file_table(
+        &sorted_files,
+        options.num_files,
+        &columns,
+        "File",
+        Color::Green,
+        None,
+    );
+    println!("{}", table.render());
+
+    let minified = files.iter().filter(|x| x.is_minified()).collect::<Vec<_>>();
+    if !minified.is_empty() {
+        println!(
+            "\n{} {} ({}), consider excluding them",
+            "Minified files:".green(),
+            minified.len(),
+            format_bytes(minified.iter().map(|x| x.bytes).sum())
+        );
+    }
+}
+
+fn display_age_info(
+    files: &[File],
+    grouped_files: &HashMap<FileType, Vec<File>>,
+    options: &TableOptions,
+) {
+    println!("\n===================================\n");
+    println!(
+        "{}\n",
+        "Age of the code based on the git history".cyan().bold()
+    );
+
+    let aged_files = files.iter().filter(|x| x.age.is_some()).collect::<Vec<_>>();
+    if aged_files.is_empty() {
+        println!("No git history found for the analyzed files");
+        return;
+    }
+
+    println!("{}", "Least recently changed files".cyan());
+    let mut stale_files = options.filter(aged_files.iter().copied());
+    options.sort(&mut stale_files, SortKey::Age);
+    let columns = options.columns(vec![Column::Path, Column::LastCommit, Column::LineAge]);
+    let table = file_table(
+        &stale_files,
+        options.num_files,
+        &columns,
+        "File",
+        Color::Cyan,
+        None,
+    );
+    println!("{}", table.render());
+
+    let mut total_buckets = [0; AGE_BUCKETS.len()];
+    for age in aged_files.iter().filter_map(|x| x.age.as_ref()) {
+        for (total, lines) in total_buckets.iter_mut().zip(age.line_buckets) {
+            *total += lines;
+        }
+    }
+    let total_lines = total_buckets.iter().sum::<usize>();
+
+    println!("\n{}", "Lines of code by age".cyan());
+    let mut histogram_table = TableBuilder::new()
+        .has_top_boarder(true)
+        .style(TableStyle::thin())
+        .build();
+    histogram_table.add_row(Row::new(vec![
+        TableCell::new_with_alignment("Age".to_string().cyan().bold(), 1, Alignment::Center),
         TableCell::new_with_alignment(
-            "Number of commits".to_string().yellow().bold(),
+            "Lines of Code".to_string().cyan().bold(),
             1,
             Alignment::Center,
         ),
+        TableCell::new_with_alignment("Share".to_string().cyan().bold(), 2, Alignment::Center),
     ]));
-    for file in largest_files {
-        commits_table.add_row(Row::new(vec![
-            file.path,
-            file.commits.unwrap_or(1).to_string(),
+    for ((label, _), lines) in AGE_BUCKETS.iter().zip(total_buckets) {
+        let share = percentage(lines, total_lines);
+        histogram_table.add_row(Row::new(vec![
+            TableCell::new(label),
+            TableCell::new(lines),
+            TableCell::new(format!("{share:.1}%")),
+            TableCell::new("█".repeat((share / 2.0).round() as usize)),
         ]));
     }
+    println!("{}", histogram_table.render());
 
-    println!("{}", commits_table.render())
+    println!("\n{}", "Share of lines by age and file type".cyan());
+    let mut sorted_entries = grouped_files
+        .iter()
+        .filter_map(|(file_type, files)| {
+            let mut buckets = [0; AGE_BUCKETS.len()];
+            for age in files.iter().filter_map(|x| x.age.as_ref()) {
+                for (total, lines) in buckets.iter_mut().zip(age.line_buckets) {
+                    *total += lines;
+                }
+            }
+            let loc = files.iter().map(|x| x.loc).sum::<usize>();
+            (buckets.iter().sum::<usize>() > 0 && loc >= options.min_loc)
+                .then_some((file_type, loc, buckets))
+        })
+        .collect::<Vec<_>>();
+    sorted_entries.sort_by_key(|(_, loc, _)| Reverse(*loc));
+    let hidden_groups = options.limit_groups(&mut sorted_entries);
+
+    let mut file_type_table = TableBuilder::new()
+        .has_top_boarder(true)
+        .style(TableStyle::thin())
+        .build();
+    let mut header = vec![TableCell::new_with_alignment(
+        "File type".to_string().cyan().bold(),
+        1,
+        Alignment::Center,
+    )];
+    for (label, _) in AGE_BUCKETS.iter() {
+        header.push(TableCell::new_with_alignment(
+            label.to_string().cyan().bold(),
+            1,
+            Alignment::Center,
+        ));
+    }
+    file_type_table.add_row(Row::new(header));
+    for (file_type, _, buckets) in sorted_entries {
+        let lines = buckets.iter().sum::<usize>();
+        let mut row = vec![file_type.to_string()];
+        for bucket in buckets {
+            row.push(format!("{:.1}%", percentage(bucket, lines)));
+        }
+        file_type_table.add_row(Row::new(row));
+    }
+    add_more_row(&mut file_type_table, hidden_groups, AGE_BUCKETS.len() + 1);
+    println!("{}", file_type_table.render());
+}
+
+// colored arrow showing the change compared to a baseline
+fn format_arrow(delta: i64) -> String {
+    match delta {
+        x if x > 0 => format!("▲ {}", format_delta(x)).green().to_string(),
+        x if x < 0 => format!("▼ {}", format_delta(x)).red().to_string(),
+        _ => "=".dimmed().to_string(),
+    }
+}
+
+fn file_arrow(file: &File, baseline: &Baseline) -> String {
+    match baseline.file_loc(&file.path) {
+        Some(loc) => format_arrow(file.loc as i64 - loc as i64),
+        None => "new".green().to_string(),
+    }
+}
+
+// the path, marked if the lines of code say little about the size of the file
+fn file_label(file: &File) -> String {
+    if file.is_minified() {
+        format!("{} {}", file.path, "(minified)".yellow())
+    } else {
+        file.path.clone()
+    }
+}
+
+pub fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        0..=1023 => format!("{bytes} B"),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+pub fn format_delta(delta: i64) -> String {
+    match delta {
+        0 => "0".to_string(),
+        x if x > 0 => format!("+{x}"),
+        x => x.to_string(),
+    }
+}
+
+// max and average complexity of all functions in the files, `-` for unsupported file types
+fn complexity_columns<'a>(files: impl Iterator<Item = &'a File>) -> Vec<String> {
+    let values = files
+        .filter_map(|x| x.complexity.as_ref())
+        .flat_map(|x| x.values())
+        .collect::<Vec<_>>();
+
+    match values.iter().max() {
+        Some(max) => vec![max.to_string(), format!("{:.1}", average(&values))],
+        None => vec!["-".to_string(), "-".to_string()],
+    }
+}
+
+pub fn percentage(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    part as f64 * 100.0 / total as f64
 }
 
-fn grouped_info(grouped_files: &HashMap<FileType, Vec<File>>, git: bool) {
+fn format_age(days: u64) -> String {
+    match days {
+        0..=60 => format!("{days} days"),
+        61..=730 => format!("{} months", days / 30),
+        _ => format!("{:.1} years", days as f64 / 365.25),
+    }
+}
+
+// converts a unix timestamp to a YYYY-MM-DD date, see http://howardhinnant.github.io/date_algorithms.html
+pub fn format_date(timestamp: u64) -> String {
+    let z = (timestamp / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn grouped_info(
+    grouped_files: &HashMap<FileType, Vec<File>>,
+    options: &DisplayOptions,
+    baseline: Option<&Baseline>,
+) {
     println!("\n===================================\n");
     println!(
         "{}\n",
         "Grouped information about the files".bright_purple().bold()
     );
 
-    let mut sorted_entries: Vec<_> = grouped_files.into_iter().collect();
-    sorted_entries.sort_by(|(_, files1), (_, files2)| files2.len().cmp(&files1.len()));
+    // files without history, like those of an archive, have no commits to show
+    let git = (options.git || options.all)
+        && grouped_files
+            .values()
+            .flatten()
+            .any(|x| x.commits.is_some());
+    let mut sorted_entries = grouped_files
+        .iter()
+        .filter(|(_, files)| files.iter().map(|x| x.loc).sum::<usize>() >= options.table.min_loc)
+        .collect::<Vec<_>>();
+    options.table.sort_groups(&mut sorted_entries);
+    let hidden_groups = options.table.limit_groups(&mut sorted_entries);
 
     let mut file_type_table = TableBuilder::new()
         .has_top_boarder(true)
         .style(TableStyle::thin())
         .build();
-    file_type_table.add_row(Row::new(vec![
+    let mut file_type_header = vec![
         TableCell::new_with_alignment(
             "File type".to_string().bright_red().bold(),
             1,
@@ -158,75 +992,77 @@ fn grouped_info(grouped_files: &HashMap<FileType, Vec<File>>, git: bool) {
             1,
             Alignment::Center,
         ),
-    ]));
+    ];
+    if baseline.is_some() {
+        file_type_header.push(TableCell::new_with_alignment(
+            "Change in files".to_string().bright_red().bold(),
+            1,
+            Alignment::Center,
+        ));
+        file_type_header.push(TableCell::new_with_alignment(
+            "Change in lines".to_string().bright_red().bold(),
+            1,
+            Alignment::Center,
+        ));
+    }
+    if options.complexity {
+        for label in ["Max complexity", "Avg complexity"] {
+            file_type_header.push(TableCell::new_with_alignment(
+                label.to_string().bright_red().bold(),
+                1,
+                Alignment::Center,
+            ));
+        }
+    }
+    let file_type_columns = file_type_header.len();
+    file_type_table.add_row(Row::new(file_type_header));
+
+    let mut default_columns = vec![Column::Path, Column::Loc];
+    if baseline.is_some() {
+        default_columns.push(Column::Change);
+    }
+    if git {
+        default_columns.push(Column::Commits);
+    }
+    if options.complexity {
+        default_columns.push(Column::Complexity);
+    }
+    let columns = options.table.columns(default_columns);
 
     let mut tables: Vec<Table> = Vec::new();
 
     for (key, val) in sorted_entries.iter() {
         let total_lines_of_code = val.iter().map(|x| x.loc).sum::<usize>();
         let number_of_files = val.len();
-        file_type_table.add_row(Row::new(vec![
+        let mut file_type_row = vec![
             key.to_string(),
             number_of_files.to_string(),
             total_lines_of_code.to_string(),
-        ]));
-        let mut table = TableBuilder::new()
-            .has_top_boarder(true)
-            .style(TableStyle::thin())
-            .build();
-        if git {
-            table.add_row(Row::new(vec![
-                TableCell::new_with_alignment(
-                    key.to_string().bright_red().bold(),
-                    1,
-                    Alignment::Center,
-                ),
-                TableCell::new_with_alignment(
-                    "Lines of Code".to_string().bright_red().bold(),
-                    1,
-                    Alignment::Center,
-                ),
-                TableCell::new_with_alignment(
-                    "Number of associated commits"
-                        .to_string()
-                        .bright_red()
-                        .bold(),
-                    1,
-                    Alignment::Center,
-                ),
-            ]));
-        } else {
-            table.add_row(Row::new(vec![
-                TableCell::new_with_alignment(
-                    key.to_string().bright_red().bold(),
-                    1,
-                    Alignment::Center,
-                ),
-                TableCell::new_with_alignment(
-                    "Lines of Code".to_string().bright_red().bold(),
-                    1,
-                    Alignment::Center,
-                ),
-            ]));
+        ];
+        if let Some(baseline) = baseline {
+            let (baseline_files, baseline_loc) = baseline.file_type_stats(key);
+            file_type_row.push(format_arrow(number_of_files as i64 - baseline_files as i64));
+            file_type_row.push(format_arrow(
+                total_lines_of_code as i64 - baseline_loc as i64,
+            ));
         }
-        let mut sorted_files: Vec<File> = val.to_vec();
-        sorted_files.sort_by(|a, b| b.loc.cmp(&a.loc));
-
-        let largest_files = sorted_files.into_iter().take(10).collect::<Vec<_>>();
-        for file in largest_files {
-            if git {
-                table.add_row(Row::new(vec![
-                    file.path,
-                    file.loc.to_string(),
-                    file.commits.unwrap_or(1).to_string(),
-                ]));
-            } else {
-                table.add_row(Row::new(vec![file.path, file.loc.to_string()]));
-            }
+        if options.complexity {
+            file_type_row.extend(complexity_columns(val.iter()));
         }
+        file_type_table.add_row(Row::new(file_type_row));
 
-        tables.push(table);
+        let mut sorted_files = options.table.filter(val.iter());
+        options.table.sort(&mut sorted_files, SortKey::Loc);
+        tables.push(file_table(
+            &sorted_files,
+            options.table.num_files,
+            &columns,
+            &key.to_string(),
+            Color::BrightRed,
+            baseline,
+        ));
     }
+    add_more_row(&mut file_type_table, hidden_groups, file_type_columns);
 
     tables.insert(0, file_type_table);
 
@@ -235,34 +1071,265 @@ fn grouped_info(grouped_files: &HashMap<FileType, Vec<File>>, git: bool) {
     }
 }
 
-fn simple_info(files: &Vec<File>, num: usize) {
+fn simple_info(files: &[File], options: &DisplayOptions, baseline: Option<&Baseline>) {
     println!("\n===================================\n");
     println!(
         "{}\n",
         "Largest files in your project".bright_green().bold()
     );
-    let mut sorted_files: Vec<File> = files.clone().to_vec();
-    sorted_files.sort_by(|a, b| b.loc.cmp(&a.loc));
+    let mut sorted_files = options.table.filter(files.iter());
+    options.table.sort(&mut sorted_files, SortKey::Loc);
+
+    let mut default_columns = vec![Column::Path, Column::Loc];
+    if baseline.is_some() {
+        default_columns.push(Column::Change);
+    }
+    if options.complexity {
+        default_columns.push(Column::Complexity);
+    }
+    let table = file_table(
+        &sorted_files,
+        options.table.num_files,
+        &options.table.columns(default_columns),
+        "File",
+        Color::BrightGreen,
+        baseline,
+    );
+    println!("{}", table.render());
+}
+
+/// Every submodule with its own totals and largest files, reported apart from the summary with
+/// `--submodules separate`.
+pub fn display_submodule_info(submodules: &[SubmoduleFiles], options: &TableOptions) {
+    for section in submodules {
+        println!("\n===================================\n");
+        println!(
+            "{} {}\n",
+            "Submodule".cyan().bold(),
+            section.submodule.path.cyan().bold()
+        );
+        println!(
+            "{} {}\n",
+            "Total number of files:".cyan(),
+            section.files.len()
+        );
+        println!(
+            "{} {}\n",
+            "Total lines of code:".cyan(),
+            section.files.iter().map(|x| x.loc).sum::<usize>()
+        );
+        let mut default_columns = vec![Column::Path, Column::Type, Column::Loc];
+        match section.total_commits {
+            Some(total_commits) => {
+                println!("{} {total_commits}\n", "Total number of commits:".cyan());
+                default_columns.push(Column::Commits);
+            }
+            None => println!("{}\n", "Not checked out, no git history found".cyan()),
+        }
+
+        let mut sorted_files = options.filter(section.files.iter());
+        options.sort(&mut sorted_files, SortKey::Loc);
+        let table = file_table(
+            &sorted_files,
+            options.num_files,
+            &options.columns(default_columns),
+            "File",
+            Color::Cyan,
+            None,
+        );
+        println!("{}", table.render());
+    }
+}
+
+/// Files that are not in the git index, reported apart from the summary with `--untracked`.
+pub fn display_untracked_info(files: &[File], options: &TableOptions) {
+    println!("\n===================================\n");
+    println!("{}\n", "Untracked files, not counted above".red().bold());
+    println!("{} {}\n", "Number of untracked files:".red(), files.len());
+    println!(
+        "{} {}\n",
+        "Untracked lines of code:".red(),
+        files.iter().map(|x| x.loc).sum::<usize>()
+    );
 
-    let largest_files = sorted_files.into_iter().take(num).collect::<Vec<_>>();
+    let mut sorted_files = options.filter(files.iter());
+    options.sort(&mut sorted_files, SortKey::Loc);
+    let table = file_table(
+        &sorted_files,
+        options.num_files,
+        &options.columns(vec![Column::Path, Column::Type, Column::Loc]),
+        "File",
+        Color::Red,
+        None,
+    );
+    println!("{}", table.render());
+}
+
+/// Header cells of a file table column, two for the complexity.
+pub fn column_headers(column: Column, path_header: &str) -> Vec<&str> {
+    match column {
+        Column::Path => vec![path_header],
+        Column::Name => vec!["Name"],
+        Column::Type => vec!["File type"],
+        Column::Loc => vec!["Lines of Code"],
+        Column::Bytes => vec!["Size"],
+        Column::Commits => vec!["Number of commits"],
+        Column::Churn => vec!["Churn"],
+        Column::Age => vec!["Age"],
+        Column::Change => vec!["Change"],
+        Column::Complexity => vec!["Max complexity", "Avg complexity"],
+        Column::LongestLine => vec!["Longest line (bytes)"],
+        Column::LineLength => vec!["Avg line length (bytes)"],
+        Column::LastCommit => vec!["Last commit"],
+        Column::LineAge => vec!["Median line age"],
+        Column::Todos => vec!["Markers"],
+        Column::Functions => vec!["Functions"],
+        Column::Types => vec!["Structs/Classes"],
+        Column::Traits => vec!["Traits/Interfaces"],
+        Column::Tests => vec!["Tests"],
+    }
+}
+
+/// Cells of a file in a file table column, `-` for values that were not collected. `now` is
+/// the unix timestamp the ages are counted up to.
+pub fn column_cells(
+    file: &File,
+    column: Column,
+    baseline: Option<&Baseline>,
+    now: u64,
+) -> Vec<String> {
+    match column {
+        Column::Path => vec![file.path.clone()],
+        Column::Name => vec![file.name.clone()],
+        Column::Type => vec![file.file_type.to_string()],
+        Column::Loc => vec![file.loc.to_string()],
+        Column::Bytes => vec![format_bytes(file.bytes)],
+        Column::Commits => vec![file.commits.unwrap_or(1).to_string()],
+        Column::Churn => vec![file
+            .churn
+            .map(|x| x.to_string())
+            .unwrap_or_else(|| "-".to_string())],
+        Column::Age => vec![file
+            .age
+            .as_ref()
+            .map(|x| format_age(now.saturating_sub(x.last_commit) / 86_400))
+            .unwrap_or_else(|| "-".to_string())],
+        Column::Change => vec![baseline
+            .map(|x| file_arrow(file, x))
+            .unwrap_or_else(|| "-".to_string())],
+        Column::Complexity => complexity_columns([file].into_iter()),
+        Column::LongestLine => vec![file.longest_line.to_string()],
+        Column::LineLength => vec![format!("{:.1}", file.average_line_length())],
+        Column::LastCommit => vec![file
+            .age
+            .as_ref()
+            .map(|x| format_date(x.last_commit))
+            .unwrap_or_else(|| "-".to_string())],
+        Column::LineAge => vec![file
+            .age
+            .as_ref()
+            .and_then(|x| x.median_line_age)
+            .map(format_age)
+            .unwrap_or_else(|| "-".to_string())],
+        Column::Todos => vec![file
+            .todos
+            .as_ref()
+            .map(|x| x.len().to_string())
+            .unwrap_or_else(|| "-".to_string())],
+        Column::Functions | Column::Types | Column::Traits | Column::Tests => {
+            vec![file
+                .symbols
+                .as_ref()
+                .map(|x| match column {
+                    Column::Functions => x.functions.len(),
+                    Column::Types => x.types,
+                    Column::Traits => x.traits,
+                    _ => x.tests,
+                })
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| "-".to_string())]
+        }
+    }
+}
+
+// table with a row for each of the first `limit` files, `path_header` names the path column
+fn file_table(
+    files: &[&File],
+    limit: usize,
+    columns: &[Column],
+    path_header: &str,
+    color: Color,
+    baseline: Option<&Baseline>,
+) -> Table<'static> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or(0);
     let mut table = TableBuilder::new()
         .has_top_boarder(true)
         .style(TableStyle::thin())
         .build();
-    table.add_row(Row::new(vec![
-        TableCell::new_with_alignment(
-            "File".to_string().bright_green().bold(),
-            1,
-            Alignment::Center,
-        ),
-        TableCell::new_with_alignment(
-            "Lines of Code".to_string().bright_green().bold(),
-            1,
-            Alignment::Center,
-        ),
-    ]));
-    for file in largest_files {
-        table.add_row(Row::new(vec![file.path, file.loc.to_string()]));
+
+    let header = columns
+        .iter()
+        .flat_map(|column| column_headers(*column, path_header))
+        .map(|x| TableCell::new_with_alignment(x.color(color).bold(), 1, Alignment::Center))
+        .collect::<Vec<_>>();
+    let header_columns = header.len();
+    table.add_row(Row::new(header));
+
+    for file in files.iter().take(limit) {
+        let row = columns
+            .iter()
+            .flat_map(|column| match column {
+                Column::Path => vec![file_label(file)],
+                _ => column_cells(file, *column, baseline, now),
+            })
+            .collect::<Vec<_>>();
+        table.add_row(Row::new(row));
+    }
+    add_more_row(
+        &mut table,
+        files.len().saturating_sub(limit),
+        header_columns,
+    );
+
+    table
+}
+
+/// Footer for the rows that were left out of a table.
+pub fn add_more_row(table: &mut Table, more: usize, columns: usize) {
+    if more > 0 {
+        table.add_row(Row::new(vec![TableCell::new_with_alignment(
+            format!("... and {more} more").dimmed(),
+            columns,
+            Alignment::Left,
+        )]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_718_409_600), "2024-06-15");
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(12), "12 days");
+        assert_eq!(format_age(400), "13 months");
+        assert_eq!(format_age(1096), "3.0 years");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(2 * 1_048_576), "2.0 MB");
     }
-    println!("{}", table.render());
 }
@@ -0,0 +1,552 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use ignore::overrides::Override;
+use serde::Serialize;
+use term_table::{
+    row::Row,
+    table_cell::{Alignment, TableCell},
+    TableBuilder, TableStyle,
+};
+
+use crate::{
+    archive::{get_archive_contents, ArchiveKind},
+    cli::DuplicatesOptions,
+    columns::{SortKey, SortOrder, TableOptions},
+    display::add_more_row,
+    file::{File, FileType},
+    revision::{list_tree, ObjectReader},
+    syntax::{comment_syntax, strip_comments},
+};
+
+const HASH_BASE: u64 = 1_000_003;
+// the files are only known by their lines, not by their history or size
+const DUPLICATES_SORT_KEYS: [SortKey; 3] = [SortKey::Loc, SortKey::Name, SortKey::Path];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicatesFormat {
+    Table,
+    Json,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct CloneLocation {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+/// A block of normalized lines that occurs in several places.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct CloneGroup {
+    pub lines: usize,
+    pub locations: Vec<CloneLocation>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FileDuplication {
+    pub path: String,
+    pub file_type: FileType,
+    pub lines: usize,
+    pub duplicated_lines: usize,
+    pub percentage: f64,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FileTypeDuplication {
+    pub file_type: FileType,
+    pub lines: usize,
+    pub duplicated_lines: usize,
+    pub percentage: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicatesReport {
+    pub min_lines: usize,
+    pub lines: usize,
+    pub duplicated_lines: usize,
+    pub percentage: f64,
+    pub files: Vec<FileDuplication>,
+    pub file_types: Vec<FileTypeDuplication>,
+    pub groups: Vec<CloneGroup>,
+}
+
+// a file reduced to its normalized lines, each with its original line number and hash
+struct Source<'a> {
+    file: &'a File,
+    lines: Vec<(usize, u64)>,
+}
+
+/// Reports the duplicated blocks of the files, reading them from the same archive, revision or
+/// directory they were analyzed from.
+pub fn run_duplicates(
+    files: &[File],
+    dir: &str,
+    archive: Option<ArchiveKind>,
+    rev: Option<&str>,
+    overrides: &Override,
+    options: &DuplicatesOptions,
+    table: &TableOptions,
+) -> Result<()> {
+    if let Some(key) = table.sort_by.filter(|x| !DUPLICATES_SORT_KEYS.contains(x)) {
+        bail!("duplicates can not sort by {key}, expected loc, name or path");
+    }
+    if table.columns.is_some() {
+        bail!("duplicates has no --columns, its tables always show the duplicated lines");
+    }
+
+    let contents = match (archive, rev) {
+        (Some(kind), _) => {
+            let mut archive_contents = get_archive_contents(dir, kind, overrides)?;
+            files
+                .iter()
+                .map(|file| {
+                    let content = archive_contents
+                        .remove(&file.path)
+                        .with_context(|| format!("{} is not in {dir}", file.path))?;
+                    Ok((file, content))
+                })
+                .collect::<Result<Vec<_>>>()?
+        }
+        (None, Some(rev)) => read_revision(files, dir, rev)?,
+        (None, None) => {
+            let mut contents = Vec::new();
+            for file in files.iter() {
+                let path = Path::new(dir).join(&file.path);
+                let content = fs::read_to_string(&path)
+                    .with_context(|| format!("could not read {}", path.display()))?;
+                contents.push((file, content));
+            }
+            contents
+        }
+    };
+
+    let mut report = find_duplicates(&contents, options.min_lines);
+    sort_files(&mut report.files, table);
+
+    let output = match options.format {
+        DuplicatesFormat::Table => render_table(&report, table),
+        DuplicatesFormat::Json => serde_json::to_string_pretty(&report)? + "\n",
+    };
+
+    match &options.output {
+        Some(path) => {
+            fs::write(path, output).with_context(|| format!("could not write to {path}"))?
+        }
+        None => print!("{output}"),
+    }
+
+    Ok(())
+}
+
+// by duplicated lines unless another key was chosen, `--asc` puts the least duplicated first
+fn sort_files(files: &mut [FileDuplication], table: &TableOptions) {
+    let order = table.order.unwrap_or(
+        table
+            .sort_by
+            .map_or(SortOrder::Descending, |x| x.default_order()),
+    );
+    let file_name = |x: &FileDuplication| {
+        Path::new(&x.path)
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+    };
+    files.sort_by(|a, b| {
+        let ordering = match table.sort_by {
+            Some(SortKey::Loc) => a.lines.cmp(&b.lines),
+            Some(SortKey::Name) => file_name(a).cmp(&file_name(b)),
+            Some(SortKey::Path) => a.path.cmp(&b.path),
+            _ => a.duplicated_lines.cmp(&b.duplicated_lines),
+        };
+        match order {
+            SortOrder::Ascending => ordering,
+            SortOrder::Descending => ordering.reverse(),
+        }
+    });
+}
+
+// the content of the files as of the revision they were analyzed at, not the working tree
+fn read_revision<'a>(files: &'a [File], dir: &str, rev: &str) -> Result<Vec<(&'a File, String)>> {
+    let oids = list_tree(dir, rev)?
+        .into_iter()
+        .map(|x| (x.path, x.oid))
+        .collect::<HashMap<_, _>>();
+    let mut reader = ObjectReader::new(dir)?;
+    let mut contents = Vec::new();
+    for file in files.iter() {
+        let oid = oids
+            .get(&file.path)
+            .with_context(|| format!("{} is not in {rev}", file.path))?;
+        let content = String::from_utf8(reader.read_blob(oid)?)
+            .with_context(|| format!("could not read {} in {rev}", file.path))?;
+        contents.push((file, content));
+    }
+    Ok(contents)
+}
+
+// lines without comments and whitespace, lines with only punctuation like `}` are skipped
+fn normalize(content: &str, file_type: FileType) -> Option<Vec<(usize, u64)>> {
+    let syntax = comment_syntax(file_type)?;
+
+    let lines = strip_comments(content, &syntax)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, line)| line.chars().any(|x| x.is_alphanumeric()))
+        .map(|(index, line)| {
+            let mut hasher = DefaultHasher::new();
+            line.chars()
+                .filter(|x| !x.is_whitespace())
+                .for_each(|x| x.hash(&mut hasher));
+            (index + 1, hasher.finish())
+        })
+        .collect();
+
+    Some(lines)
+}
+
+/// Finds blocks of at least `min_lines` normalized lines that occur more than once, using a
+/// rolling hash over the line hashes of every file.
+pub fn find_duplicates(contents: &[(&File, String)], min_lines: usize) -> DuplicatesReport {
+    let min_lines = min_lines.max(1);
+    let sources = contents
+        .iter()
+        .filter_map(|(file, content)| {
+            normalize(content, file.file_type).map(|lines| Source { file, lines })
+        })
+        .collect::<Vec<_>>();
+
+    let mut windows: HashMap<u64, Vec<(usize, usize)>> = HashMap::new();
+    let highest_power = HASH_BASE.wrapping_pow(min_lines as u32 - 1);
+    for (source_index, source) in sources.iter().enumerate() {
+        if source.lines.len() < min_lines {
+            continue;
+        }
+        let mut hash = 0u64;
+        for (index, (_, line_hash)) in source.lines.iter().enumerate() {
+            if index >= min_lines {
+                let (_, removed) = source.lines[index - min_lines];
+                hash = hash.wrapping_sub(removed.wrapping_mul(highest_power));
+            }
+            hash = hash.wrapping_mul(HASH_BASE).wrapping_add(*line_hash);
+            if index + 1 >= min_lines {
+                let start = index + 1 - min_lines;
+                windows.entry(hash).or_default().push((source_index, start));
+            }
+        }
+    }
+
+    let line_hashes = |(source, start): (usize, usize)| {
+        sources[source].lines[start..start + min_lines]
+            .iter()
+            .map(|(_, hash)| *hash)
+            .collect::<Vec<_>>()
+    };
+
+    let mut duplicated_windows = Vec::new();
+    for occurrences in windows.into_values().filter(|x| x.len() > 1) {
+        // hash collisions are possible, so the lines of every occurrence are compared
+        let first = line_hashes(occurrences[0]);
+        let mut kept: Vec<(usize, usize)> = Vec::new();
+        for occurrence in occurrences {
+            let overlaps = kept.last().is_some_and(|(source, start)| {
+                *source == occurrence.0 && occurrence.1 < start + min_lines
+            });
+            if !overlaps && line_hashes(occurrence) == first {
+                kept.push(occurrence);
+            }
+        }
+        if kept.len() > 1 {
+            duplicated_windows.push(kept);
+        }
+    }
+    duplicated_windows.sort();
+
+    // consecutive windows with the same locations, each shifted by one line, form one clone
+    let mut clones: Vec<(Vec<(usize, usize)>, usize)> = Vec::new();
+    let mut active: HashMap<Vec<(usize, usize)>, usize> = HashMap::new();
+    for window in duplicated_windows {
+        let index = match active.remove(&window) {
+            Some(index) => {
+                clones[index].1 += 1;
+                index
+            }
+            None => {
+                clones.push((window.clone(), min_lines));
+                clones.len() - 1
+            }
+        };
+        let next = window
+            .iter()
+            .map(|(source, start)| (*source, start + 1))
+            .collect();
+        active.insert(next, index);
+    }
+
+    let mut duplicated = sources
+        .iter()
+        .map(|x| vec![false; x.lines.len()])
+        .collect::<Vec<_>>();
+    for (occurrences, length) in clones.iter() {
+        for (source, start) in occurrences.iter() {
+            duplicated[*source][*start..start + length].fill(true);
+        }
+    }
+
+    let mut groups = clones
+        .iter()
+        .map(|(occurrences, length)| CloneGroup {
+            lines: *length,
+            locations: occurrences
+                .iter()
+                .map(|(source, start)| {
+                    let lines = &sources[*source].lines;
+                    CloneLocation {
+                        path: sources[*source].file.path.clone(),
+                        start_line: lines[*start].0,
+                        end_line: lines[start + length - 1].0,
+                    }
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    groups.sort_by(|a, b| {
+        let copied_lines = |x: &CloneGroup| x.lines * (x.locations.len() - 1);
+        copied_lines(b)
+            .cmp(&copied_lines(a))
+            .then_with(|| a.locations[0].path.cmp(&b.locations[0].path))
+    });
+
+    let mut files = Vec::new();
+    let mut file_types: BTreeMap<FileType, (usize, usize)> = BTreeMap::new();
+    for (source, duplicated) in sources.iter().zip(duplicated) {
+        let lines = source.lines.len();
+        let duplicated_lines = duplicated.iter().filter(|x| **x).count();
+        let entry = file_types.entry(source.file.file_type).or_default();
+        entry.0 += lines;
+        entry.1 += duplicated_lines;
+
+        if duplicated_lines > 0 {
+            files.push(FileDuplication {
+                path: source.file.path.clone(),
+                file_type: source.file.file_type,
+                lines,
+                duplicated_lines,
+                percentage: percentage(duplicated_lines, lines),
+            });
+        }
+    }
+    files.sort_by(|a, b| {
+        b.duplicated_lines
+            .cmp(&a.duplicated_lines)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    let mut file_types = file_types
+        .into_iter()
+        .map(
+            |(file_type, (lines, duplicated_lines))| FileTypeDuplication {
+                file_type,
+                lines,
+                duplicated_lines,
+                percentage: percentage(duplicated_lines, lines),
+            },
+        )
+        .collect::<Vec<_>>();
+    file_types.sort_by_key(|x| Reverse(x.duplicated_lines));
+
+    let lines = file_types.iter().map(|x| x.lines).sum();
+    let duplicated_lines = file_types.iter().map(|x| x.duplicated_lines).sum();
+    DuplicatesReport {
+        min_lines,
+        lines,
+        duplicated_lines,
+        percentage: percentage(duplicated_lines, lines),
+        files,
+        file_types,
+        groups,
+    }
+}
+
+fn percentage(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    part as f64 * 100.0 / total as f64
+}
+
+fn header(labels: &[&str]) -> Row<'static> {
+    Row::new(
+        labels
+            .iter()
+            .map(|x| TableCell::new_with_alignment(x.bright_cyan().bold(), 1, Alignment::Center))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn render_table(report: &DuplicatesReport, table: &TableOptions) -> String {
+    let mut output = String::new();
+    writeln!(
+        output,
+        "\n{} {}\n",
+        "Duplicated blocks of at least".bright_cyan().bold(),
+        format!("{} lines", report.min_lines).bright_cyan().bold()
+    )
+    .unwrap();
+    writeln!(
+        output,
+        "{} {} of {} ({:.1}%)\n",
+        "Duplicated lines:".bright_cyan(),
+        report.duplicated_lines,
+        report.lines,
+        report.percentage
+    )
+    .unwrap();
+
+    let mut file_type_table = TableBuilder::new()
+        .has_top_boarder(true)
+        .style(TableStyle::thin())
+        .build();
+    file_type_table.add_row(header(&["File type", "Lines", "Duplicated lines", "Share"]));
+    for x in report.file_types.iter() {
+        file_type_table.add_row(Row::new(vec![
+            x.file_type.to_string(),
+            x.lines.to_string(),
+            x.duplicated_lines.to_string(),
+            format!("{:.1}%", x.percentage),
+        ]));
+    }
+    writeln!(output, "{}", file_type_table.render()).unwrap();
+
+    if report.groups.is_empty() {
+        writeln!(output, "No duplicated blocks found").unwrap();
+        return output;
+    }
+
+    writeln!(
+        output,
+        "\n{}",
+        "Files with the most duplicated lines".bright_cyan()
+    )
+    .unwrap();
+    let mut file_table = TableBuilder::new()
+        .has_top_boarder(true)
+        .style(TableStyle::thin())
+        .build();
+    file_table.add_row(header(&["File", "Lines", "Duplicated lines", "Share"]));
+    let files = report
+        .files
+        .iter()
+        .filter(|x| x.lines >= table.min_loc)
+        .collect::<Vec<_>>();
+    for x in files.iter().take(table.num_files) {
+        file_table.add_row(Row::new(vec![
+            x.path.clone(),
+            x.lines.to_string(),
+            x.duplicated_lines.to_string(),
+            format!("{:.1}%", x.percentage),
+        ]));
+    }
+    add_more_row(
+        &mut file_table,
+        files.len().saturating_sub(table.num_files),
+        4,
+    );
+    writeln!(output, "{}", file_table.render()).unwrap();
+
+    writeln!(output, "\n{}", "Largest clone groups".bright_cyan()).unwrap();
+    let mut group_table = TableBuilder::new()
+        .has_top_boarder(true)
+        .style(TableStyle::thin())
+        .build();
+    group_table.add_row(header(&["Lines", "Copies", "Locations"]));
+    for group in report.groups.iter().take(table.num_files) {
+        let locations = group
+            .locations
+            .iter()
+            .map(|x| format!("{}:{}-{}", x.path, x.start_line, x.end_line))
+            .collect::<Vec<_>>();
+        group_table.add_row(Row::new(vec![
+            group.lines.to_string(),
+            group.locations.len().to_string(),
+            locations.join("\n"),
+        ]));
+    }
+    add_more_row(
+        &mut group_table,
+        report.groups.len().saturating_sub(table.num_files),
+        3,
+    );
+    writeln!(output, "{}", group_table.render()).unwrap();
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::file_from_content;
+
+    #[test]
+    fn test_find_duplicates() {
+        let block = "let total = items.iter().sum();\nlet average = total / items.len();\nprintln!(\"{average}\");\n";
+        let a = format!("fn a(items: &[u32]) {{\n{block}}}\n");
+        let b = format!(
+            "// copied from a\nfn b(items: &[u32]) {{\n    // spacing and comments differ\n{}}}\n",
+            block.replace("= ", "=  ")
+        );
+        let c = String::from("fn c() {\n    let total = 1;\n}\n");
+        let contents = [
+            (&file_from_content("src/a.rs", &a), a.clone()),
+            (&file_from_content("src/b.rs", &b), b.clone()),
+            (&file_from_content("src/c.rs", &c), c.clone()),
+            (&file_from_content("README.md", &a), a.clone()),
+        ];
+
+        let report = find_duplicates(&contents, 3);
+        assert_eq!(
+            report.groups,
+            vec![CloneGroup {
+                lines: 3,
+                locations: vec![
+                    CloneLocation {
+                        path: String::from("src/a.rs"),
+                        start_line: 2,
+                        end_line: 4,
+                    },
+                    CloneLocation {
+                        path: String::from("src/b.rs"),
+                        start_line: 4,
+                        end_line: 6,
+                    },
+                ],
+            }]
+        );
+        assert_eq!(report.lines, 10);
+        assert_eq!(report.duplicated_lines, 6);
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.files[0].percentage, 75.0);
+
+        // the function signatures differ, so four lines are never equal
+        assert!(find_duplicates(&contents, 4).groups.is_empty());
+
+        let mut files = report.files;
+        let table = TableOptions {
+            sort_by: Some(SortKey::Path),
+            order: Some(SortOrder::Descending),
+            ..Default::default()
+        };
+        sort_files(&mut files, &table);
+        assert_eq!(
+            files.iter().map(|x| x.path.as_str()).collect::<Vec<_>>(),
+            ["src/b.rs", "src/a.rs"]
+        );
+    }
+}
@@ -1,13 +1,37 @@
 use std::{
+    collections::{HashMap, HashSet},
     ffi::OsString,
     fmt::{self, Display},
     fs,
+    path::Path,
 };
 
-use ignore::{DirEntry, WalkBuilder};
+use ignore::{
+    overrides::{Override, OverrideBuilder},
+    DirEntry, WalkBuilder,
+};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cache::Cache,
+    complexity::{complexity, Complexity},
+    counter::{count_source, SourceStats},
+    git::AgeInfo,
+    symbols::{symbols, Symbols},
+    testing::{test_loc, test_modules},
+    todos::{find_todos, TodoMarker},
+};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+// bundlers and minifiers put whole files on a few lines, hand written code stays far below
+const MINIFIED_LINE_LENGTH: f64 = 200.0;
+// a single line this long is generated, even between short lines like in a bundle with a banner
+const MINIFIED_LONGEST_LINE: usize = 5_000;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum FileType {
     Arduino,
     Astro,
@@ -48,14 +72,43 @@ pub enum FileType {
     Other,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct File {
     pub name: String,
     pub path: String,
     pub loc: usize,
+    /// FNV-1a hash of the content
+    pub hash: u64,
+    pub bytes: usize,
+    /// Bytes in the longest line, without the line break
+    pub longest_line: usize,
+    /// Bytes in all lines, without the line breaks
+    pub line_bytes: usize,
     pub extension: OsString,
     pub file_type: FileType,
     pub commits: Option<usize>,
+    pub churn: Option<usize>,
+    pub age: Option<AgeInfo>,
+    pub complexity: Option<Complexity>,
+    pub source: Option<SourceStats>,
+    pub symbols: Option<Symbols>,
+    /// Lines of test code, all lines for test files
+    pub test_loc: Option<usize>,
+    /// Files of the Rust test modules declared out of line, e.g. with `#[cfg(test)] mod tests;`
+    #[serde(default)]
+    pub test_modules: Vec<String>,
+    pub todos: Option<Vec<TodoMarker>>,
+}
+
+/// Optional passes over the content of every file, run while the lines are counted.
+#[derive(Debug, Default, Clone)]
+pub struct AnalysisOptions {
+    pub complexity: bool,
+    pub source: bool,
+    pub symbols: bool,
+    pub tests: bool,
+    /// Markers to look for in comments
+    pub todos: Option<Regex>,
 }
 
 impl File {
@@ -120,9 +173,54 @@ impl File {
         }
     }
 
+    /// Bytes per line, without the line breaks like [`File::longest_line`].
+    pub fn average_line_length(&self) -> f64 {
+        match self.loc {
+            0 => 0.0,
+            loc => self.line_bytes as f64 / loc as f64,
+        }
+    }
+
+    /// Whether the file looks like the output of a minifier, based on its name, its average line
+    /// length or a single very long line.
+    pub fn is_minified(&self) -> bool {
+        self.name.contains(".min.")
+            || self.average_line_length() > MINIFIED_LINE_LENGTH
+            || self.longest_line > MINIFIED_LONGEST_LINE
+    }
+
     pub fn add_commits(&mut self, commits: usize) {
         self.commits = Some(commits)
     }
+
+    pub fn add_churn(&mut self, churn: usize) {
+        self.churn = Some(churn)
+    }
+
+    pub fn add_age(&mut self, age: AgeInfo) {
+        self.age = Some(age)
+    }
+
+    pub fn add_complexity(&mut self, content: &str) {
+        self.complexity = complexity(content, self.file_type)
+    }
+
+    pub fn add_source_stats(&mut self, content: &str) {
+        self.source = count_source(&self.path, content, self.file_type)
+    }
+
+    pub fn add_symbols(&mut self, content: &str) {
+        self.symbols = symbols(content, self.file_type)
+    }
+
+    pub fn add_test_loc(&mut self, content: &str) {
+        self.test_loc = Some(test_loc(&self.path, content, self.file_type, self.loc));
+        self.test_modules = test_modules(&self.path, content, self.file_type);
+    }
+
+    pub fn add_todos(&mut self, content: &str, markers: &Regex) {
+        self.todos = find_todos(content, self.file_type, markers)
+    }
 }
 
 impl Display for FileType {
@@ -131,25 +229,64 @@ impl Display for FileType {
     }
 }
 
+/// Overrides for the walk of `dir` from the `--exclude` and `--match` globs.
+pub fn build_overrides(
+    dir: &str,
+    exclude: &[String],
+    matches: &[String],
+) -> Result<Override, ignore::Error> {
+    let mut builder = OverrideBuilder::new(dir);
+    for glob in exclude.iter() {
+        let glob = format!("!{glob}"); // add ! to the front to exclude the glob
+        builder.add(glob.as_str())?;
+    }
+    for glob in matches.iter() {
+        builder.add(glob)?;
+    }
+    builder.build()
+}
+
 pub fn get_files(
     dir: &str,
     overrides: ignore::overrides::Override,
     skip_lockfiles: &bool,
+    analysis: &AnalysisOptions,
+    cache: Option<&Cache>,
+) -> Vec<File> {
+    get_files_below(
+        dir,
+        Path::new(dir),
+        overrides,
+        skip_lockfiles,
+        analysis,
+        cache,
+    )
+}
+
+/// Files below `start`, a file or directory inside `dir`, with their paths relative to `dir`.
+/// `start` itself is not checked against the ignore rules.
+pub fn get_files_below(
+    dir: &str,
+    start: &Path,
+    overrides: ignore::overrides::Override,
+    skip_lockfiles: &bool,
+    analysis: &AnalysisOptions,
+    cache: Option<&Cache>,
 ) -> Vec<File> {
     let mut files: Vec<File> = Vec::new();
 
-    for result in WalkBuilder::new(dir)
+    for result in WalkBuilder::new(start)
         .overrides(overrides)
         .hidden(false)
         .build()
     {
         match result {
             Ok(entry) => {
-                if entry.path().to_str().unwrap().contains(".git/") {
+                // the `.git` file of a submodule points to its git directory
+                if entry.path().to_str().unwrap().contains(".git/") || entry.file_name() == ".git" {
                     continue;
                 }
-                if let Some(mut file) = get_file_info(&entry, dir) {
-                    file.add_file_type();
+                if let Some(file) = get_file_info(&entry, dir, analysis, cache) {
                     if *skip_lockfiles && file.file_type == FileType::Lockfile {
                         continue;
                     }
@@ -163,34 +300,142 @@ pub fn get_files(
     files
 }
 
-fn get_file_info(entry: &DirEntry, dir: &str) -> Option<File> {
-    let entry = entry.clone();
-    let path = entry
-        .path()
-        .strip_prefix(dir)
-        .unwrap()
-        .to_str()
-        .unwrap()
-        .to_string();
-    let file_name = entry.file_name().to_str().unwrap().to_string();
-    let extension = match entry.path().extension() {
+// files unchanged since the cached run are taken from the cache, by their metadata without
+// reading them, or by their content without analyzing them
+fn get_file_info(
+    entry: &DirEntry,
+    dir: &str,
+    analysis: &AnalysisOptions,
+    cache: Option<&Cache>,
+) -> Option<File> {
+    let path = entry.path().strip_prefix(dir).unwrap().to_str().unwrap();
+
+    if let (Some(cache), Ok(metadata)) = (cache, entry.metadata()) {
+        if let Some(file) = cache.get(path, &metadata) {
+            return Some(file.clone());
+        }
+    }
+
+    match fs::read_to_string(entry.path()) {
+        Ok(content) => {
+            if let Some(file) = cache.and_then(|x| x.get_by_content(path, &content)) {
+                return Some(file.clone());
+            }
+            Some(analyze_content(path, &content, analysis))
+        }
+        Err(_) => None,
+    }
+}
+
+/// Builds the `File` for the content at `path` and runs the chosen analyses on it.
+pub fn analyze_content(path: &str, content: &str, analysis: &AnalysisOptions) -> File {
+    let mut file = file_from_content(path, content);
+    if analysis.complexity {
+        file.add_complexity(content);
+    }
+    if analysis.source {
+        file.add_source_stats(content);
+    }
+    if analysis.symbols {
+        file.add_symbols(content);
+    }
+    if analysis.tests {
+        file.add_test_loc(content);
+    }
+    if let Some(markers) = &analysis.todos {
+        file.add_todos(content, markers);
+    }
+    file
+}
+
+pub fn file_from_content(path: &str, content: &str) -> File {
+    let file_path = Path::new(path);
+    let file_name = file_path
+        .file_name()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = match file_path.extension() {
         Some(ext) => ext.to_owned(),
         None => OsString::from("config"),
     };
 
-    if fs::read_to_string(entry.path()).is_ok() {
-        let lines = fs::read_to_string(entry.path()).unwrap().lines().count() as usize;
-        return Some(File {
-            name: file_name,
-            path,
-            extension,
-            loc: lines,
-            commits: None,
-            file_type: FileType::Other,
-        });
+    let mut file = File {
+        name: file_name,
+        path: path.to_string(),
+        extension,
+        loc: content.lines().count(),
+        hash: content_hash(content),
+        bytes: content.len(),
+        longest_line: content.lines().map(|x| x.len()).max().unwrap_or(0),
+        line_bytes: content.lines().map(|x| x.len()).sum(),
+        commits: None,
+        churn: None,
+        age: None,
+        complexity: None,
+        source: None,
+        symbols: None,
+        test_loc: None,
+        test_modules: Vec::new(),
+        todos: None,
+        file_type: FileType::Other,
+    };
+    file.add_file_type();
+
+    file
+}
+
+/// Stable 64-bit FNV-1a hash, the same across runs and platforms.
+pub fn content_hash(content: &str) -> u64 {
+    content.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Groups of at least two non-empty files with the same size and content hash, each sorted
+/// by path, with the groups wasting the most lines first. The contents are not compared byte
+/// by byte, as only the hash is kept.
+pub fn identical_files(files: &[File]) -> Vec<Vec<&File>> {
+    let mut by_hash: HashMap<(u64, usize, usize), Vec<&File>> = HashMap::new();
+    for file in files.iter().filter(|x| x.loc > 0) {
+        by_hash
+            .entry((file.hash, file.bytes, file.loc))
+            .or_default()
+            .push(file);
     }
 
-    None
+    let mut groups = by_hash
+        .into_values()
+        .filter(|x| x.len() > 1)
+        .map(|mut group| {
+            group.sort_by(|a, b| a.path.cmp(&b.path));
+            group
+        })
+        .collect::<Vec<_>>();
+    groups.sort_by(|a, b| {
+        wasted_loc(b)
+            .cmp(&wasted_loc(a))
+            .then_with(|| a[0].path.cmp(&b[0].path))
+    });
+    groups
+}
+
+/// Lines of all copies but the first.
+pub fn wasted_loc(group: &[&File]) -> usize {
+    group.iter().skip(1).map(|x| x.loc).sum()
+}
+
+/// The files with only the first copy of every group of identical files.
+pub fn unique_files(files: &[File]) -> Vec<File> {
+    let copies = identical_files(files)
+        .into_iter()
+        .flat_map(|group| group.into_iter().skip(1).map(|x| x.path.clone()))
+        .collect::<HashSet<_>>();
+
+    files
+        .iter()
+        .filter(|x| !copies.contains(&x.path))
+        .cloned()
+        .collect()
 }
 
 #[cfg(test)]
@@ -203,8 +448,20 @@ mod tests {
             name: String::from("foo.rs"),
             path: String::from("foo.rs"),
             loc: 12,
+            hash: 0,
+            bytes: 0,
+            longest_line: 0,
+            line_bytes: 0,
             extension: OsString::from("rs"),
             commits: None,
+            churn: None,
+            age: None,
+            complexity: None,
+            source: None,
+            symbols: None,
+            test_loc: None,
+            test_modules: Vec::new(),
+            todos: None,
             file_type: FileType::Other,
         };
         file.add_file_type();
@@ -215,8 +472,20 @@ mod tests {
             name: String::from("Cargo.lock"),
             path: String::from("Cargo.lock"),
             loc: 12,
+            hash: 0,
+            bytes: 0,
+            longest_line: 0,
+            line_bytes: 0,
             extension: OsString::from("lock"),
             commits: None,
+            churn: None,
+            age: None,
+            complexity: None,
+            source: None,
+            symbols: None,
+            test_loc: None,
+            test_modules: Vec::new(),
+            todos: None,
             file_type: FileType::Other,
         };
         file.add_file_type();
@@ -227,12 +496,86 @@ mod tests {
             name: String::from("pnpm-lock.yaml"),
             path: String::from("pnpm-lock.yaml"),
             loc: 12,
+            hash: 0,
+            bytes: 0,
+            longest_line: 0,
+            line_bytes: 0,
             extension: OsString::from("yaml"),
             commits: None,
+            churn: None,
+            age: None,
+            complexity: None,
+            source: None,
+            symbols: None,
+            test_loc: None,
+            test_modules: Vec::new(),
+            todos: None,
             file_type: FileType::Other,
         };
         file.add_file_type();
 
         assert_eq!(file.file_type, FileType::Lockfile)
     }
+
+    #[test]
+    fn test_identical_files() {
+        let files = vec![
+            file_from_content("b/config.json", "{\n  \"a\": 1\n}\n"),
+            file_from_content("a/config.json", "{\n  \"a\": 1\n}\n"),
+            file_from_content("c/config.json", "{\n  \"a\": 2\n}\n"),
+            file_from_content("a/__init__.py", ""),
+            file_from_content("b/__init__.py", ""),
+        ];
+        assert_eq!(content_hash(""), FNV_OFFSET_BASIS);
+        assert_ne!(files[0].hash, files[2].hash);
+
+        let groups = identical_files(&files);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups[0]
+                .iter()
+                .map(|x| x.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a/config.json", "b/config.json"]
+        );
+        assert_eq!(wasted_loc(&groups[0]), 3);
+        // a hash collision with a file of another size is no copy
+        let mut collision = file_from_content("d/config.json", "{\n  \"a\": 10\n}\n");
+        collision.hash = files[0].hash;
+        assert!(identical_files(&[files[0].clone(), collision]).is_empty());
+        assert_eq!((files[0].bytes, files[0].longest_line), (13, 8));
+        assert!(!files[0].is_minified());
+
+        let unique = unique_files(&files);
+        assert_eq!(
+            unique.iter().map(|x| x.path.as_str()).collect::<Vec<_>>(),
+            vec![
+                "a/config.json",
+                "c/config.json",
+                "a/__init__.py",
+                "b/__init__.py"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_minified() {
+        let bundle = format!("(function(){{{}}})();\n", "var a=1;".repeat(100));
+        let file = file_from_content("dist/app.js", &bundle);
+        assert_eq!(file.loc, 1);
+        assert_eq!(file.longest_line, 817);
+        assert!(file.is_minified());
+        assert!(file_from_content("vendor/lib.min.js", "var a;\n").is_minified());
+        assert!(!file_from_content("src/app.js", "const a = 1;\n").is_minified());
+
+        let data = format!("{}\n{}", "x".repeat(10_000), "const a = 1;\n".repeat(100));
+        let file = file_from_content("src/data.js", &data);
+        assert!(file.average_line_length() < MINIFIED_LINE_LENGTH);
+        assert!(file.is_minified());
+
+        // both lengths count bytes without the line breaks, also for characters longer than one
+        // byte
+        let file = file_from_content("src/i18n.txt", "äöü\r\nab\n");
+        assert_eq!((file.longest_line, file.average_line_length()), (6, 4.0));
+    }
 }
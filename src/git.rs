@@ -1,13 +1,32 @@
-use std::{collections::HashMap, process::Command};
+use std::{
+    collections::{HashMap, HashSet},
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use crate::file::File;
+use crate::{config::AuthorConfig, file::File};
 use nom::{
-    bytes::complete::take_till,
-    character::complete::{self, line_ending, multispace0, multispace1, not_line_ending},
+    bytes::complete::{take_till, take_until},
+    character::complete::{self, char, line_ending, multispace0, multispace1, not_line_ending},
     multi::separated_list1,
-    sequence::{preceded, separated_pair},
+    sequence::{delimited, preceded, separated_pair},
     IResult,
 };
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+pub const AGE_BUCKETS: [(&str, u64); 6] = [
+    ("< 1 month", 30),
+    ("1-6 months", 182),
+    ("6-12 months", 365),
+    ("1-2 years", 730),
+    ("2-5 years", 1826),
+    ("> 5 years", u64::MAX),
+];
+
+const BOT_PATTERNS: [&str; 4] = [r"\[bot\]", "dependabot", "renovate", "github-actions"];
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct GitAuthor {
@@ -15,63 +34,430 @@ pub struct GitAuthor {
     pub commits: u32,
 }
 
-pub fn add_git_info(files: &mut Vec<File>, dir: &str) -> Option<usize> {
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct AgeInfo {
+    /// Unix timestamp of the last commit touching the file
+    pub last_commit: u64,
+    /// Median age of the lines in days, based on git blame
+    pub median_line_age: Option<u64>,
+    /// Number of lines per entry in `AGE_BUCKETS`
+    pub line_buckets: [usize; AGE_BUCKETS.len()],
+}
+
+/// Totals per file of the history up to a commit.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryCounts {
+    pub head: String,
+    pub total: usize,
+    pub files: HashMap<String, usize>,
+}
+
+/// Commits and churn per file from earlier runs, see [`add_git_info`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitCache {
+    pub commits: Option<HistoryCounts>,
+    pub churn: Option<HistoryCounts>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthorGrouping {
+    #[default]
+    Name,
+    Email,
+    Domain,
+}
+
+#[derive(Debug, Default)]
+pub struct AuthorOptions {
+    pub grouping: AuthorGrouping,
+    aliases: HashMap<String, String>,
+    exclude: Vec<Regex>,
+}
+
+impl AuthorOptions {
+    pub fn new(
+        config: &AuthorConfig,
+        grouping: Option<AuthorGrouping>,
+        exclude: &[String],
+        skip_bots: bool,
+    ) -> anyhow::Result<Self> {
+        let mut aliases = HashMap::new();
+        for (canonical, names) in config.aliases.iter() {
+            aliases.insert(canonical.to_lowercase(), canonical.to_owned());
+            for name in names.iter() {
+                aliases.insert(name.to_lowercase(), canonical.to_owned());
+            }
+        }
+
+        let mut patterns: Vec<&str> = config
+            .exclude
+            .iter()
+            .chain(exclude.iter())
+            .map(|x| x.as_str())
+            .collect();
+        if skip_bots {
+            patterns.extend(BOT_PATTERNS);
+        }
+        let exclude = patterns
+            .into_iter()
+            .map(|pattern| RegexBuilder::new(pattern).case_insensitive(true).build())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(AuthorOptions {
+            grouping: grouping.or(config.group_by).unwrap_or_default(),
+            aliases,
+            exclude,
+        })
+    }
+
+    fn is_excluded(&self, identity: &str) -> bool {
+        self.exclude.iter().any(|x| x.is_match(identity))
+    }
+
+    fn group_key(&self, name: &str, email: &str) -> String {
+        match self.grouping {
+            AuthorGrouping::Name => self
+                .aliases
+                .get(&name.to_lowercase())
+                .or_else(|| self.aliases.get(&email.to_lowercase()))
+                .cloned()
+                .unwrap_or_else(|| name.to_string()),
+            AuthorGrouping::Email => email.to_lowercase(),
+            AuthorGrouping::Domain => match email.rsplit_once('@') {
+                Some((_, domain)) => domain.to_lowercase(),
+                None => email.to_lowercase(),
+            },
+        }
+    }
+}
+
+/// Whether `dir` is inside a git work tree.
+pub fn is_git_repository(dir: &str) -> bool {
+    Command::new("git")
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .current_dir(dir)
+        .output()
+        .is_ok_and(|x| x.status.success())
+}
+
+/// Paths of the files in the git index, relative to `dir` like the files of a directory walk.
+pub fn tracked_files(dir: &str) -> anyhow::Result<HashSet<String>> {
+    let output = Command::new("git")
+        .args(["ls-files", "-z"])
+        .current_dir(dir)
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "could not list the tracked files of {dir}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|x| !x.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Adds the number of commits up to `rev`, or HEAD, to every file and returns the total number
+/// of commits. With a cache, only the commits since the cached commit are counted.
+pub fn add_git_info(
+    files: &mut [File],
+    dir: &str,
+    rev: Option<&str>,
+    cache: Option<&mut GitCache>,
+) -> Option<usize> {
+    let (total_commits, file_map) =
+        cached_counts(dir, rev, cache.map(|x| &mut x.commits), commit_counts)?;
+
+    for file in files.iter_mut() {
+        let commits = match file_map.get(file.path.as_str()) {
+            Some(x) => *x,
+            _ => 1,
+        };
+        file.add_commits(commits)
+    }
+
+    Some(total_commits)
+}
+
+pub fn add_churn_info(
+    files: &mut [File],
+    dir: &str,
+    rev: Option<&str>,
+    cache: Option<&mut GitCache>,
+) {
+    let counts = cached_counts(dir, rev, cache.map(|x| &mut x.churn), churn_counts);
+    if let Some((_, churn_map)) = counts {
+        for file in files.iter_mut() {
+            file.add_churn(*churn_map.get(file.path.as_str()).unwrap_or(&0));
+        }
+    }
+}
+
+// total and value per file of a range of the history
+type Counts = (usize, HashMap<String, usize>);
+
+// the history up to `tip` since a commit, or all of it if there is none
+fn log_range(since: Option<&str>, tip: &str) -> String {
+    since.map_or(tip.to_string(), |x| format!("{x}..{tip}"))
+}
+
+fn commit_counts(dir: &str, since: Option<&str>, tip: &str) -> Option<Counts> {
+    let command = format!(
+        "git log {} --pretty=format: --name-only | sort | uniq -c",
+        log_range(since, tip)
+    );
     let commit_output = if cfg!(target_os = "windows") {
         Command::new("cmd")
-            .args([
-                "/C",
-                "git log --pretty=format: --name-only | sort | uniq -c",
-            ])
+            .args(["/C", command.as_str()])
             .current_dir(dir)
             .output()
             .expect("failed to execute process")
     } else {
         Command::new("sh")
             .arg("-c")
-            .arg("git log --pretty=format: --name-only | sort | uniq -c")
+            .arg(command)
             .current_dir(dir)
             .output()
             .expect("failed to execute process")
     };
 
-    if commit_output.status.success() {
-        let output_str = String::from_utf8_lossy(&commit_output.stdout);
-        let (_, (total_commits, file_map)) =
-            parse_git_commits(&output_str).expect("should parse commits");
+    if !commit_output.status.success() {
+        return None;
+    }
+    let output_str = String::from_utf8_lossy(&commit_output.stdout);
+    if output_str.trim().is_empty() {
+        return Some((0, HashMap::new()));
+    }
+    // the count of blank separator lines leads the sorted output, a single commit has none
+    let output_str = match output_str.lines().next() {
+        Some(line) if line.trim().parse::<usize>().is_ok() => output_str.to_string(),
+        _ => format!("0\n{output_str}"),
+    };
+    let (_, (_, file_map)) = parse_git_commits(&output_str).expect("should parse commits");
+    let total_commits = count_commits(dir, since, tip)?;
 
-        for file in files.iter_mut() {
-            let commits = match file_map.get(file.path.as_str()) {
-                Some(x) => *x as usize,
-                _ => 1,
-            };
-            file.add_commits(commits)
-        }
+    let file_map = file_map
+        .into_iter()
+        .map(|(path, commits)| (path.to_string(), commits as usize))
+        .collect();
+    Some((total_commits, file_map))
+}
+
+fn count_commits(dir: &str, since: Option<&str>, tip: &str) -> Option<usize> {
+    let output = Command::new("git")
+        .args(["rev-list", "--count", log_range(since, tip).as_str()])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+fn churn_counts(dir: &str, since: Option<&str>, tip: &str) -> Option<Counts> {
+    let churn_output = Command::new("git")
+        .args([
+            "log",
+            log_range(since, tip).as_str(),
+            "--numstat",
+            "--format=",
+        ])
+        .current_dir(dir)
+        .output()
+        .expect("failed to execute process");
 
-        return Some(total_commits);
+    if !churn_output.status.success() {
+        return None;
     }
+    let output_str = String::from_utf8_lossy(&churn_output.stdout);
+    let churn_map = parse_churn(&output_str)
+        .into_iter()
+        .map(|(path, churn)| (path.to_string(), churn))
+        .collect::<HashMap<_, _>>();
+    Some((churn_map.values().sum(), churn_map))
+}
 
-    None
+// counts of the history up to `rev`, reused from the cache as long as the commit is the same
+// and only counted for the new commits if it is a descendant of the cached one
+fn cached_counts(
+    dir: &str,
+    rev: Option<&str>,
+    cached: Option<&mut Option<HistoryCounts>>,
+    count: fn(&str, Option<&str>, &str) -> Option<Counts>,
+) -> Option<Counts> {
+    // the resolved commit id is passed on, as the counts run through a shell
+    let head = resolve_commit(dir, rev)?;
+    let Some(cached) = cached else {
+        return count(dir, None, &head);
+    };
+
+    let counts = match cached.take() {
+        Some(counts) if counts.head == head => counts,
+        Some(mut counts) if is_ancestor(dir, &counts.head, &head) => {
+            let (total, files) = count(dir, Some(&counts.head), &head)?;
+            counts.total += total;
+            for (path, value) in files {
+                *counts.files.entry(path).or_default() += value;
+            }
+            counts.head = head;
+            counts
+        }
+        _ => {
+            let (total, files) = count(dir, None, &head)?;
+            HistoryCounts { head, total, files }
+        }
+    };
+
+    let result = (counts.total, counts.files.clone());
+    *cached = Some(counts);
+    Some(result)
 }
 
-pub fn get_git_authors(dir: &str, num: usize) -> Option<Vec<GitAuthor>> {
-    let author_output = if cfg!(target_os = "windows") {
-        Command::new("cmd")
-            .args(["/C", "git log --format='%aN' | sort | uniq -c | sort -nr"])
+// the commit id of `rev`, or of HEAD
+fn resolve_commit(dir: &str, rev: Option<&str>) -> Option<String> {
+    let rev = format!("{}^{{commit}}", rev.unwrap_or("HEAD"));
+    let output = Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet", "--end-of-options", &rev])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn is_ancestor(dir: &str, ancestor: &str, commit: &str) -> bool {
+    Command::new("git")
+        .args(["merge-base", "--is-ancestor", ancestor, commit])
+        .current_dir(dir)
+        .output()
+        .is_ok_and(|x| x.status.success())
+}
+
+pub fn add_age_info(files: &mut [File], dir: &str, rev: Option<&str>) {
+    let log_output = Command::new("git")
+        .args([
+            "log",
+            rev.unwrap_or("HEAD"),
+            "--format=%x00%at",
+            "--name-only",
+        ])
+        .current_dir(dir)
+        .output()
+        .expect("failed to execute process");
+
+    if !log_output.status.success() {
+        return;
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or(0);
+    let output_str = String::from_utf8_lossy(&log_output.stdout);
+    let last_commits = parse_last_commits(&output_str);
+
+    for file in files.iter_mut() {
+        let Some(last_commit) = last_commits.get(file.path.as_str()) else {
+            continue;
+        };
+
+        let mut args = vec!["blame", "--line-porcelain"];
+        args.extend(rev);
+        args.extend(["--", file.path.as_str()]);
+        let blame_output = Command::new("git")
+            .args(args)
             .current_dir(dir)
             .output()
-            .expect("failed to execute process")
-    } else {
-        Command::new("sh")
-            .arg("-c")
-            .arg("git log --format='%aN' | sort | uniq -c | sort -nr")
+            .expect("failed to execute process");
+        let line_times = if blame_output.status.success() {
+            parse_blame_times(&String::from_utf8_lossy(&blame_output.stdout))
+        } else {
+            Vec::new()
+        };
+
+        file.add_age(line_age_info(*last_commit, line_times, now));
+    }
+}
+
+/// Adds the author and time of the lines with todo markers, one blame per file with markers.
+pub fn add_todo_blame(files: &mut [File], dir: &str, rev: Option<&str>) {
+    for file in files.iter_mut() {
+        let Some(todos) = file.todos.as_mut().filter(|x| !x.is_empty()) else {
+            continue;
+        };
+
+        let mut args = vec![String::from("blame"), String::from("--line-porcelain")];
+        for todo in todos.iter() {
+            args.push(format!("-L{},{}", todo.line, todo.line));
+        }
+        args.extend(rev.map(String::from));
+        args.push(String::from("--"));
+        args.push(file.path.clone());
+
+        let blame_output = Command::new("git")
+            .args(&args)
             .current_dir(dir)
             .output()
-            .expect("failed to execute process")
-    };
+            .expect("failed to execute process");
+        if !blame_output.status.success() {
+            continue;
+        }
+
+        let lines = parse_blame_lines(&String::from_utf8_lossy(&blame_output.stdout));
+        for todo in todos.iter_mut() {
+            todo.blame = lines.get(&todo.line).cloned();
+        }
+    }
+}
+
+fn line_age_info(last_commit: u64, line_times: Vec<u64>, now: u64) -> AgeInfo {
+    let mut ages = line_times
+        .into_iter()
+        .map(|x| now.saturating_sub(x) / SECONDS_PER_DAY)
+        .collect::<Vec<u64>>();
+    ages.sort();
+
+    let mut line_buckets = [0; AGE_BUCKETS.len()];
+    for age in ages.iter() {
+        line_buckets[age_bucket(*age)] += 1;
+    }
+
+    AgeInfo {
+        last_commit,
+        median_line_age: ages.get(ages.len() / 2).copied(),
+        line_buckets,
+    }
+}
+
+pub fn age_bucket(days: u64) -> usize {
+    AGE_BUCKETS
+        .iter()
+        .position(|(_, max_days)| days < *max_days)
+        .unwrap_or(AGE_BUCKETS.len() - 1)
+}
+
+// %aN and %aE already resolve identities through the repository .mailmap
+pub fn get_git_authors(
+    dir: &str,
+    rev: Option<&str>,
+    num: usize,
+    options: &AuthorOptions,
+) -> Option<Vec<GitAuthor>> {
+    let head = resolve_commit(dir, rev)?;
+    // without a shell, `<` and `>` would be redirections for cmd.exe on Windows
+    let author_output = Command::new("git")
+        .args(["log", head.as_str(), "--format=%aN <%aE>"])
+        .current_dir(dir)
+        .output()
+        .expect("failed to execute process");
 
     if author_output.status.success() {
         let output_str = String::from_utf8_lossy(&author_output.stdout);
-        let (_, authors) = parse_git_authors(&output_str).expect("should parse authors");
+        let authors = normalize_authors(count_authors(&output_str), options);
 
         return Some(authors.into_iter().take(num).collect::<Vec<GitAuthor>>());
     }
@@ -79,25 +465,125 @@ pub fn get_git_authors(dir: &str, num: usize) -> Option<Vec<GitAuthor>> {
     None
 }
 
-fn parse_git_authors(input: &str) -> IResult<&str, Vec<GitAuthor>> {
-    let (input, authors) = separated_list1(
-        line_ending,
-        separated_pair(
-            preceded(multispace0, complete::u32),
-            multispace1,
-            not_line_ending,
-        ),
-    )(input)?;
+// one line per commit, most commits first
+fn count_authors(input: &str) -> Vec<GitAuthor> {
+    let mut counts: HashMap<&str, u32> = HashMap::new();
+    for line in input.lines().filter(|x| !x.is_empty()) {
+        *counts.entry(line).or_insert(0) += 1;
+    }
 
-    let authors = authors
-        .iter()
-        .map(|(commits, name)| GitAuthor {
+    let mut authors = counts
+        .into_iter()
+        .map(|(name, commits)| GitAuthor {
             name: name.to_string(),
-            commits: *commits,
+            commits,
         })
-        .collect();
+        .collect::<Vec<_>>();
+    authors.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.name.cmp(&b.name)));
 
-    Ok((input, authors))
+    authors
+}
+
+fn normalize_authors(authors: Vec<GitAuthor>, options: &AuthorOptions) -> Vec<GitAuthor> {
+    let mut grouped: HashMap<String, u32> = HashMap::new();
+
+    for author in authors.iter() {
+        if options.is_excluded(&author.name) {
+            continue;
+        }
+        let (name, email) = match parse_identity(&author.name) {
+            Ok((_, (name, email))) => (name.trim(), email),
+            _ => (author.name.as_str(), ""),
+        };
+        *grouped.entry(options.group_key(name, email)).or_insert(0) += author.commits;
+    }
+
+    let mut authors = grouped
+        .into_iter()
+        .map(|(name, commits)| GitAuthor { name, commits })
+        .collect::<Vec<_>>();
+    authors.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.name.cmp(&b.name)));
+
+    authors
+}
+
+fn parse_identity(input: &str) -> IResult<&str, (&str, &str)> {
+    let (input, name) = take_until(" <")(input)?;
+    let (input, email) = preceded(
+        char(' '),
+        delimited(char('<'), take_till(|x| x == '>'), char('>')),
+    )(input)?;
+
+    Ok((input, (name, email)))
+}
+
+// git log lists the newest commits first, so the first timestamp seen for a file is its last change
+fn parse_last_commits(input: &str) -> HashMap<&str, u64> {
+    let mut last_commits: HashMap<&str, u64> = HashMap::new();
+    let mut timestamp = 0;
+
+    for line in input.lines() {
+        if let Some(time) = line.strip_prefix('\0') {
+            timestamp = time.trim().parse::<u64>().unwrap_or(0);
+        } else if !line.trim().is_empty() {
+            last_commits.entry(line.trim()).or_insert(timestamp);
+        }
+    }
+
+    last_commits
+}
+
+// sums up the added and removed lines per file, binary files show up as "-\t-\tpath"
+fn parse_churn(input: &str) -> HashMap<&str, usize> {
+    let mut churn_map: HashMap<&str, usize> = HashMap::new();
+
+    for line in input.lines() {
+        let mut parts = line.splitn(3, '\t');
+        if let (Some(added), Some(removed), Some(path)) = (parts.next(), parts.next(), parts.next())
+        {
+            let changed =
+                added.parse::<usize>().unwrap_or(0) + removed.parse::<usize>().unwrap_or(0);
+            *churn_map.entry(path.trim()).or_insert(0) += changed;
+        }
+    }
+
+    churn_map
+}
+
+fn parse_blame_times(input: &str) -> Vec<u64> {
+    input
+        .lines()
+        .filter_map(|line| line.strip_prefix("author-time "))
+        .filter_map(|time| time.trim().parse::<u64>().ok())
+        .collect()
+}
+
+// final line number mapped to the author and author time of the line
+fn parse_blame_lines(input: &str) -> HashMap<usize, (String, u64)> {
+    let mut lines = HashMap::new();
+    let mut line = None;
+    let mut author = String::new();
+
+    for entry in input.lines() {
+        if let Some(name) = entry.strip_prefix("author ") {
+            author = name.to_string();
+        } else if let Some(time) = entry.strip_prefix("author-time ") {
+            if let (Some(line), Ok(time)) = (line, time.trim().parse::<u64>()) {
+                lines.insert(line, (author.clone(), time));
+            }
+        } else if !entry.starts_with('\t') {
+            // headers are `<sha> <original line> <final line> [<lines in group>]`
+            let mut parts = entry.split(' ');
+            let is_header = parts
+                .next()
+                .is_some_and(|x| x.len() == 40 && x.chars().all(|c| c.is_ascii_hexdigit()));
+            if is_header {
+                line = parts.nth(1).and_then(|x| x.parse::<usize>().ok());
+            }
+        }
+    }
+
+    lines
 }
 
 fn parse_git_commits(input: &str) -> IResult<&str, (usize, HashMap<&str, u32>)> {
@@ -146,21 +632,135 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_authors() {
-        let test_str = "12 DerTimonius\n4 dependabot";
+    fn test_count_authors() {
+        let test_str = "DerTimonius <tim@example.com>\ndependabot <bot@example.com>\nDerTimonius <tim@example.com>\n";
+
+        let authors = vec![
+            GitAuthor {
+                name: String::from("DerTimonius <tim@example.com>"),
+                commits: 2,
+            },
+            GitAuthor {
+                name: String::from("dependabot <bot@example.com>"),
+                commits: 1,
+            },
+        ];
+        assert_eq!(count_authors(test_str), authors);
+    }
+
+    #[test]
+    fn test_normalize_authors() {
+        let authors = vec![
+            GitAuthor {
+                name: String::from("Jane Doe <jane@example.com>"),
+                commits: 5,
+            },
+            GitAuthor {
+                name: String::from("jane <jane@users.noreply.github.com>"),
+                commits: 3,
+            },
+            GitAuthor {
+                name: String::from("John <john@contractor.io>"),
+                commits: 4,
+            },
+            GitAuthor {
+                name: String::from("dependabot[bot] <support@github.com>"),
+                commits: 20,
+            },
+        ];
+        let config = AuthorConfig {
+            aliases: HashMap::from([(String::from("Jane Doe"), vec![String::from("jane")])]),
+            ..Default::default()
+        };
 
-        assert!(parse_git_authors(test_str).is_ok());
+        let options = AuthorOptions::new(&config, None, &[], true).unwrap();
+        assert_eq!(
+            normalize_authors(authors, &options),
+            vec![
+                GitAuthor {
+                    name: String::from("Jane Doe"),
+                    commits: 8,
+                },
+                GitAuthor {
+                    name: String::from("John"),
+                    commits: 4,
+                },
+            ]
+        );
 
         let authors = vec![
             GitAuthor {
-                name: String::from("DerTimonius"),
-                commits: 12,
+                name: String::from("Jane Doe <jane@Example.com>"),
+                commits: 5,
+            },
+            GitAuthor {
+                name: String::from("Max <max@example.com>"),
+                commits: 2,
             },
             GitAuthor {
-                name: String::from("dependabot"),
+                name: String::from("John <john@contractor.io>"),
                 commits: 4,
             },
         ];
-        assert_eq!(parse_git_authors(test_str), Ok(("", authors)));
+        let options = AuthorOptions::new(
+            &AuthorConfig::default(),
+            Some(AuthorGrouping::Domain),
+            &[String::from("^john")],
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            normalize_authors(authors, &options),
+            vec![GitAuthor {
+                name: String::from("example.com"),
+                commits: 7,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_last_commits() {
+        let test_str = "\x00300\n\nfoo.rs\nbar.rs\n\x00200\n\nfoo.rs\nbaz.rs\n";
+
+        let mut last_commits: HashMap<&str, u64> = HashMap::new();
+        last_commits.insert("foo.rs", 300);
+        last_commits.insert("bar.rs", 300);
+        last_commits.insert("baz.rs", 200);
+        assert_eq!(parse_last_commits(test_str), last_commits);
+    }
+
+    #[test]
+    fn test_parse_churn() {
+        let test_str = "10\t2\tsrc/main.rs\n\n3\t0\tREADME.md\n-\t-\tlogo.png\n1\t1\tsrc/main.rs\n";
+
+        let mut churn_map: HashMap<&str, usize> = HashMap::new();
+        churn_map.insert("src/main.rs", 14);
+        churn_map.insert("README.md", 3);
+        churn_map.insert("logo.png", 0);
+        assert_eq!(parse_churn(test_str), churn_map);
+    }
+
+    #[test]
+    fn test_parse_blame_lines() {
+        let sha = "a".repeat(40);
+        let blame = format!("{sha} 1 3 1\nauthor DerTimonius\nauthor-mail <t@example.com>\nauthor-time 86400\n\t// TODO: a\n{sha} 5 9 1\nauthor Jane Doe\nauthor-time 172800\n\t// FIXME\n");
+        let lines = parse_blame_lines(&blame);
+
+        assert_eq!(lines.get(&3), Some(&(String::from("DerTimonius"), 86400)));
+        assert_eq!(lines.get(&9), Some(&(String::from("Jane Doe"), 172800)));
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn test_line_age_info() {
+        let now = 1000 * SECONDS_PER_DAY;
+        let blame = "abc 1 1 1\nauthor DerTimonius\nauthor-time 86400000\n\tfn main() {}\nabc 2 2\nauthor-time 86400000\n\t}\ndef 3 3 1\nauthor-time 0\n\t// old\n";
+        let line_times = parse_blame_times(blame);
+        assert_eq!(line_times, vec![86_400_000, 86_400_000, 0]);
+
+        let age = line_age_info(86_400_000, line_times, now);
+        assert_eq!(age.median_line_age, Some(0));
+        assert_eq!(age.line_buckets, [2, 0, 0, 0, 1, 0]);
+        assert_eq!(age_bucket(5000), AGE_BUCKETS.len() - 1);
     }
 }
@@ -0,0 +1,384 @@
+use std::{collections::BTreeMap, fmt::Write, fs, process::Command};
+
+use anyhow::{bail, Context, Result};
+use colored::{Color, Colorize};
+use ignore::overrides::Override;
+use serde::Serialize;
+
+use crate::{
+    cli::HistoryOptions,
+    display::format_date,
+    file::FileType,
+    revision::{get_files_at_revision, ObjectReader},
+};
+
+const SECONDS_PER_WEEK: u64 = 7 * 86_400;
+const CHART_WIDTH: usize = 60;
+const COLORS: [(Color, &str); 8] = [
+    (Color::Blue, "#4e79a7"),
+    (Color::Yellow, "#f28e2b"),
+    (Color::Red, "#e15759"),
+    (Color::Cyan, "#76b7b2"),
+    (Color::Green, "#59a14f"),
+    (Color::BrightYellow, "#edc948"),
+    (Color::Magenta, "#b07aa1"),
+    (Color::BrightRed, "#ff9da7"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sampling {
+    Commits(usize),
+    Weekly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFormat {
+    Chart,
+    Json,
+    Csv,
+    Svg,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Commit {
+    hash: String,
+    timestamp: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryPoint {
+    pub commit: String,
+    pub date: String,
+    pub timestamp: u64,
+    pub total: usize,
+    pub loc: BTreeMap<FileType, usize>,
+}
+
+pub fn run_history(
+    dir: &str,
+    overrides: &Override,
+    skip_lockfiles: &bool,
+    options: &HistoryOptions,
+) -> Result<()> {
+    let commits = sample_commits(list_commits(dir, &options.rev)?, options.sampling);
+    let mut reader = ObjectReader::new(dir)?;
+    let mut points: Vec<HistoryPoint> = Vec::new();
+
+    for commit in commits {
+        let files =
+            get_files_at_revision(&mut reader, dir, &commit.hash, overrides, skip_lockfiles)?;
+
+        let mut loc: BTreeMap<FileType, usize> = BTreeMap::new();
+        for file in files.iter() {
+            if options.file_types.is_empty()
+                || options
+                    .file_types
+                    .iter()
+                    .any(|x| x.eq_ignore_ascii_case(&file.file_type.to_string()))
+            {
+                *loc.entry(file.file_type).or_insert(0) += file.loc;
+            }
+        }
+
+        points.push(HistoryPoint {
+            commit: commit.hash,
+            date: format_date(commit.timestamp),
+            timestamp: commit.timestamp,
+            total: loc.values().sum(),
+            loc,
+        });
+    }
+
+    let output = match options.format {
+        HistoryFormat::Chart => render_chart(&points),
+        HistoryFormat::Json => serde_json::to_string_pretty(&points)? + "\n",
+        HistoryFormat::Csv => render_csv(&points),
+        HistoryFormat::Svg => render_svg(&points),
+    };
+
+    match &options.output {
+        Some(path) => {
+            fs::write(path, output).with_context(|| format!("could not write to {path}"))?
+        }
+        None => print!("{output}"),
+    }
+
+    Ok(())
+}
+
+// only follows the first parent, so merged feature branches do not show up as separate samples
+fn list_commits(dir: &str, rev: &str) -> Result<Vec<Commit>> {
+    let output = Command::new("git")
+        .args(["log", "--first-parent", "--format=%H %ct", rev, "--"])
+        .current_dir(dir)
+        .output()
+        .context("failed to execute git log")?;
+
+    if !output.status.success() {
+        bail!(
+            "could not read the history of {rev}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(parse_commits(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_commits(input: &str) -> Vec<Commit> {
+    input
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter_map(|(hash, timestamp)| {
+            Some(Commit {
+                hash: hash.to_string(),
+                timestamp: timestamp.trim().parse::<u64>().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Picks the commits to analyze from a newest first list, always including the latest commit.
+/// The samples are returned oldest first.
+fn sample_commits(commits: Vec<Commit>, sampling: Sampling) -> Vec<Commit> {
+    let mut samples: Vec<Commit> = Vec::new();
+
+    for (index, commit) in commits.into_iter().enumerate() {
+        let keep = match (sampling, samples.last()) {
+            (Sampling::Commits(every), _) => index % every.max(1) == 0,
+            (Sampling::Weekly, Some(last)) => commit.timestamp + SECONDS_PER_WEEK <= last.timestamp,
+            (Sampling::Weekly, None) => true,
+        };
+        if keep {
+            samples.push(commit);
+        }
+    }
+
+    samples.reverse();
+    samples
+}
+
+fn file_types(points: &[HistoryPoint]) -> Vec<FileType> {
+    let mut totals: BTreeMap<FileType, usize> = BTreeMap::new();
+    for point in points.iter() {
+        for (file_type, loc) in point.loc.iter() {
+            let total = totals.entry(*file_type).or_insert(0);
+            *total = (*total).max(*loc);
+        }
+    }
+
+    let mut file_types = totals.into_iter().collect::<Vec<_>>();
+    file_types
+        .sort_by(|(type_a, loc_a), (type_b, loc_b)| loc_b.cmp(loc_a).then(type_a.cmp(type_b)));
+    file_types.into_iter().map(|(x, _)| x).collect()
+}
+
+fn render_csv(points: &[HistoryPoint]) -> String {
+    let file_types = file_types(points);
+    let mut output = String::from("commit,date");
+    for file_type in file_types.iter() {
+        write!(output, ",{file_type}").unwrap();
+    }
+    output.push_str(",total\n");
+
+    for point in points.iter() {
+        write!(output, "{},{}", point.commit, point.date).unwrap();
+        for file_type in file_types.iter() {
+            write!(output, ",{}", point.loc.get(file_type).unwrap_or(&0)).unwrap();
+        }
+        writeln!(output, ",{}", point.total).unwrap();
+    }
+
+    output
+}
+
+// one stacked bar per sample, the largest file types get their own color and the rest is grouped
+fn render_chart(points: &[HistoryPoint]) -> String {
+    let file_types = file_types(points);
+    let max_total = points.iter().map(|x| x.total).max().unwrap_or(0).max(1);
+    let mut output = String::new();
+
+    let mut legend = Vec::new();
+    for (file_type, (color, _)) in file_types.iter().zip(COLORS) {
+        legend.push(format!("{} {file_type}", "█".color(color)));
+    }
+    if file_types.len() > COLORS.len() {
+        legend.push(format!("{} Other", "░".white()));
+    }
+    writeln!(output, "\n{}\n", legend.join("  ")).unwrap();
+
+    for point in points.iter() {
+        let mut bar = String::new();
+        let mut other = point.total;
+        for (file_type, (color, _)) in file_types.iter().zip(COLORS) {
+            let loc = *point.loc.get(file_type).unwrap_or(&0);
+            other -= loc;
+            bar += &"█"
+                .repeat(loc * CHART_WIDTH / max_total)
+                .color(color)
+                .to_string();
+        }
+        bar += &"░"
+            .repeat(other * CHART_WIDTH / max_total)
+            .white()
+            .to_string();
+
+        writeln!(
+            output,
+            "{} {} {bar} {}",
+            point.date,
+            &point.commit[..7.min(point.commit.len())],
+            point.total
+        )
+        .unwrap();
+    }
+
+    output
+}
+
+fn render_svg(points: &[HistoryPoint]) -> String {
+    let (width, height, margin) = (800.0, 400.0, 60.0);
+    let file_types = file_types(points);
+    let max_loc = points
+        .iter()
+        .flat_map(|x| x.loc.values())
+        .max()
+        .copied()
+        .unwrap_or(0)
+        .max(1) as f64;
+    let step = (width - 2.0 * margin) / (points.len().max(2) - 1) as f64;
+    let x = |index: usize| margin + index as f64 * step;
+    let y = |loc: usize| height - margin - loc as f64 / max_loc * (height - 2.0 * margin);
+
+    let mut output = String::new();
+    writeln!(
+        output,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif" font-size="12">"#
+    )
+    .unwrap();
+    writeln!(
+        output,
+        r##"<rect width="100%" height="100%" fill="#ffffff"/>
+<line x1="{margin}" y1="{bottom}" x2="{right}" y2="{bottom}" stroke="#333333"/>
+<line x1="{margin}" y1="{margin}" x2="{margin}" y2="{bottom}" stroke="#333333"/>
+<text x="{label_x}" y="{top}" text-anchor="end">{max_loc}</text>
+<text x="{label_x}" y="{bottom}" text-anchor="end">0</text>"##,
+        bottom = height - margin,
+        right = width - margin,
+        label_x = margin - 5.0,
+        top = margin + 4.0,
+    )
+    .unwrap();
+
+    if let (Some(first), Some(last)) = (points.first(), points.last()) {
+        writeln!(
+            output,
+            r#"<text x="{margin}" y="{date_y}">{}</text>
+<text x="{right}" y="{date_y}" text-anchor="end">{}</text>"#,
+            first.date,
+            last.date,
+            date_y = height - margin + 20.0,
+            right = width - margin,
+        )
+        .unwrap();
+    }
+
+    for (index, file_type) in file_types.iter().enumerate() {
+        let (_, color) = COLORS[index % COLORS.len()];
+        let line = points
+            .iter()
+            .enumerate()
+            .map(|(i, point)| {
+                format!(
+                    "{:.1},{:.1}",
+                    x(i),
+                    y(*point.loc.get(file_type).unwrap_or(&0))
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(
+            output,
+            r#"<polyline fill="none" stroke="{color}" stroke-width="2" points="{line}"/>
+<text x="{legend_x}" y="{legend_y}" fill="{color}">{file_type}</text>"#,
+            legend_x = width - margin + 5.0,
+            legend_y = margin + 15.0 * index as f64,
+        )
+        .unwrap();
+    }
+    output.push_str("</svg>\n");
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commits() -> Vec<Commit> {
+        // newest first, like git log
+        [(5, 30), (4, 22), (3, 20), (2, 10), (1, 0)]
+            .into_iter()
+            .map(|(hash, day)| Commit {
+                hash: hash.to_string(),
+                timestamp: day * 86_400,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_commits() {
+        assert_eq!(
+            parse_commits("abc 1700000000\ndef 1600000000\n"),
+            vec![
+                Commit {
+                    hash: String::from("abc"),
+                    timestamp: 1_700_000_000,
+                },
+                Commit {
+                    hash: String::from("def"),
+                    timestamp: 1_600_000_000,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sample_commits() {
+        let hashes =
+            |commits: Vec<Commit>| commits.into_iter().map(|x| x.hash).collect::<Vec<String>>();
+
+        assert_eq!(
+            hashes(sample_commits(commits(), Sampling::Commits(2))),
+            vec!["1", "3", "5"]
+        );
+        assert_eq!(
+            hashes(sample_commits(commits(), Sampling::Weekly)),
+            vec!["1", "2", "4", "5"]
+        );
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let points = vec![
+            HistoryPoint {
+                commit: String::from("abc"),
+                date: String::from("2024-01-01"),
+                timestamp: 0,
+                total: 10,
+                loc: BTreeMap::from([(FileType::JavaScript, 10)]),
+            },
+            HistoryPoint {
+                commit: String::from("def"),
+                date: String::from("2024-01-08"),
+                timestamp: 0,
+                total: 30,
+                loc: BTreeMap::from([(FileType::JavaScript, 5), (FileType::TypeScript, 25)]),
+            },
+        ];
+
+        assert_eq!(
+            render_csv(&points),
+            "commit,date,TypeScript,JavaScript,total\nabc,2024-01-01,0,10,10\ndef,2024-01-08,25,5,30\n"
+        );
+    }
+}
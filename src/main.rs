@@ -1,34 +1,351 @@
+pub mod archive;
+pub mod baseline;
+pub mod cache;
+pub mod check;
 pub mod cli;
+pub mod columns;
+pub mod complexity;
+pub mod config;
+pub mod counter;
+pub mod diff;
 pub mod display;
+pub mod duplicates;
 pub mod file;
 pub mod git;
+pub mod history;
+#[cfg(feature = "tree-sitter")]
+pub mod parser;
+pub mod repos;
+pub mod revision;
+pub mod sarif;
+pub mod submodules;
+pub mod symbols;
+pub mod syntax;
+#[cfg(test)]
+pub mod temp_dir;
+pub mod testing;
+pub mod todos;
+pub mod tui;
+pub mod watch;
 
-use display::display_info;
-use ignore::overrides::OverrideBuilder;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
 
-use crate::{cli::run_cli, file::get_files, git::add_git_info};
+use display::{display_info, display_submodule_info, display_untracked_info};
+
+use crate::{
+    archive::{get_archive_files, ArchiveKind},
+    baseline::{load_baseline, save_baseline},
+    cache::Cache,
+    check::{check_rules, has_errors, load_rules, report_violations, EXIT_VIOLATIONS},
+    cli::{run_cli, Cli, DisplayOptions, SubCommand},
+    config::{config_path, load_config},
+    diff::run_diff,
+    duplicates::run_duplicates,
+    file::{build_overrides, get_files, AnalysisOptions, File},
+    git::{
+        add_age_info, add_churn_info, add_git_info, add_todo_blame, get_git_authors,
+        is_git_repository, tracked_files, AuthorOptions, GitCache,
+    },
+    history::run_history,
+    repos::{load_manifest, run_repos},
+    revision::get_analyzed_files_at_revision,
+    submodules::{
+        add_submodules_git_info, exclude_submodules, find_submodules, split_submodules,
+        SubmoduleFiles, SubmoduleMode,
+    },
+    todos::marker_regex,
+    tui::run_tui,
+    watch::watch_files,
+};
 
 fn main() {
     let cli = run_cli().unwrap();
     let dir = cli.dir.as_str();
+    let config = load_config(cli.config.as_deref(), dir).unwrap();
+
+    let overrides = build_overrides(dir, &cli.exclude, &cli.matches).unwrap();
+
+    let author_options = AuthorOptions::new(
+        &config.authors,
+        cli.author_grouping,
+        &cli.exclude_authors,
+        cli.skip_bots,
+    )
+    .unwrap();
 
-    let mut builder = OverrideBuilder::new(dir);
-    for glob in cli.exclude.iter() {
-        let glob = format!("!{glob}"); // add ! to the front to exclude the glob
-        builder.add(glob.as_str()).unwrap();
+    if cli.dirs.len() > 1 || cli.manifest.is_some() {
+        let mut dirs = cli.dirs.clone();
+        if let Some(manifest) = &cli.manifest {
+            dirs.extend(load_manifest(manifest).unwrap());
+        }
+        run_repos(&dirs, &cli, &author_options).unwrap();
+        return;
     }
-    for glob in cli.matches.iter() {
-        builder.add(glob).unwrap();
+
+    let skip_lockfiles = &cli.display_options.skip_lockfiles;
+    match &cli.command {
+        Some(SubCommand::History(options)) => {
+            run_history(dir, &overrides, skip_lockfiles, options).unwrap();
+            return;
+        }
+        Some(SubCommand::Diff(options)) => {
+            run_diff(
+                dir,
+                &overrides,
+                skip_lockfiles,
+                options,
+                &cli.display_options.table,
+            )
+            .unwrap();
+            return;
+        }
+        _ => {}
     }
-    let overrides = builder.build().unwrap();
 
-    let mut files = get_files(dir, overrides, &cli.display_options.skip_lockfiles);
+    let analysis = AnalysisOptions {
+        complexity: cli.display_options.complexity,
+        source: cli.display_options.breakdown,
+        symbols: cli.display_options.symbols,
+        tests: cli.display_options.tests,
+        todos: cli.display_options.todos.then(|| {
+            let mut markers = config.todos.markers.clone();
+            markers.extend(cli.todo_markers.iter().cloned());
+            marker_regex(&markers).unwrap()
+        }),
+    };
+    let rev = cli.display_options.rev.as_deref();
+    let archive = ArchiveKind::from_path(dir);
+    let mut cache = (!cli.no_cache && archive.is_none()).then(|| Cache::load(dir, &analysis));
+    let mut files = match (archive, rev) {
+        (Some(kind), _) => {
+            get_archive_files(dir, kind, &overrides, skip_lockfiles, &analysis).unwrap()
+        }
+        (None, Some(rev)) => {
+            get_analyzed_files_at_revision(dir, rev, &overrides, skip_lockfiles, &analysis).unwrap()
+        }
+        (None, None) => {
+            let files = get_files(
+                dir,
+                overrides.clone(),
+                skip_lockfiles,
+                &analysis,
+                cache.as_ref(),
+            );
+            if let Some(cache) = cache.as_mut() {
+                cache.update_files(dir, &files);
+            }
+            files
+        }
+    };
+
+    // files are split again on every change, as they can be added to the index in the meantime
+    let watched_files = cli.watch.then(|| files.clone());
+    let submodules = split_submodules_of(&mut files, &cli);
+    let untracked = split_untracked(&mut files, dir, &cli.display_options);
+
+    if let Some(SubCommand::Tui) = &cli.command {
+        let table = &cli.display_options.table;
+        let total_commits = if is_git_repository(dir) {
+            add_git_info(&mut files, dir, rev, cache.as_mut().map(|x| &mut x.git))
+        } else {
+            None
+        };
+        if total_commits.is_some() && table.needs_churn() {
+            add_churn_info(&mut files, dir, rev, cache.as_mut().map(|x| &mut x.git));
+        }
+        if total_commits.is_some() && table.needs_age() {
+            add_age_info(&mut files, dir, rev);
+        }
+        if let Some(cache) = &cache {
+            cache.save();
+        }
+        let authors = total_commits
+            .and_then(|_| get_git_authors(dir, rev, usize::MAX, &author_options))
+            .unwrap_or_default();
+        run_tui(files, authors, dir, total_commits, table).unwrap();
+        return;
+    }
+
+    if let Some(SubCommand::Duplicates(options)) = &cli.command {
+        if let Some(cache) = &cache {
+            cache.save();
+        }
+        run_duplicates(
+            &files,
+            dir,
+            archive,
+            rev,
+            &overrides,
+            options,
+            &cli.display_options.table,
+        )
+        .unwrap();
+        return;
+    }
 
-    let total_commits = if cli.display_options.git || cli.display_options.all {
-        add_git_info(&mut files, dir)
+    if let Some(SubCommand::Check(options)) = &cli.command {
+        let rules = match &options.rules {
+            Some(path) => load_rules(path).unwrap(),
+            None => config.check,
+        };
+        let baseline = cli.baseline.as_deref().map(|x| load_baseline(x).unwrap());
+        if rules.needs_baseline() && baseline.is_none() {
+            eprintln!("max_growth rules are skipped, as they need a --baseline to compare against");
+        }
+        if rules.needs_commits() {
+            add_git_info(&mut files, dir, rev, cache.as_mut().map(|x| &mut x.git));
+        }
+        if rules.needs_churn() {
+            add_churn_info(&mut files, dir, rev, cache.as_mut().map(|x| &mut x.git));
+        }
+        if let Some(cache) = &cache {
+            cache.save();
+        }
+
+        let rules_path = match &options.rules {
+            Some(path) => Some(Path::new(path).to_path_buf()),
+            None => config_path(cli.config.as_deref(), dir),
+        };
+        // SARIF locations are relative to the analyzed directory, both paths are canonicalized
+        // as the rules path is relative to the working directory
+        let rules_path = rules_path.map(|x| {
+            let x = fs::canonicalize(&x).unwrap_or(x);
+            let dir = fs::canonicalize(dir).unwrap_or_else(|_| PathBuf::from(dir));
+            match x.strip_prefix(&dir) {
+                Ok(relative) => relative.to_string_lossy().to_string(),
+                Err(_) => x.to_string_lossy().to_string(),
+            }
+        });
+
+        let violations = check_rules(&files, &rules, baseline.as_ref()).unwrap();
+        report_violations(&violations, options, rules_path.as_deref()).unwrap();
+        if has_errors(&violations) {
+            process::exit(EXIT_VIOLATIONS);
+        }
+        return;
+    }
+
+    if cli.watch {
+        if let Some(cache) = &cache {
+            cache.save();
+        }
+        let files = watched_files.unwrap_or_default();
+        watch_files(dir, &overrides, skip_lockfiles, &analysis, files, |files| {
+            let mut files = files.clone();
+            let submodules = split_submodules_of(&mut files, &cli);
+            let untracked = split_untracked(&mut files, dir, &cli.display_options);
+            report(
+                &mut files,
+                &cli,
+                &author_options,
+                None,
+                &untracked,
+                &submodules,
+            )
+        })
+        .unwrap();
+        return;
+    }
+
+    report(
+        &mut files,
+        &cli,
+        &author_options,
+        cache.as_mut().map(|x| &mut x.git),
+        &untracked,
+        &submodules,
+    );
+    if let Some(cache) = &cache {
+        cache.save();
+    }
+}
+
+// adds the git information needed by the display options and prints the summary
+fn report(
+    files: &mut [File],
+    cli: &Cli,
+    author_options: &AuthorOptions,
+    mut git_cache: Option<&mut GitCache>,
+    untracked: &[File],
+    submodules: &[SubmoduleFiles],
+) {
+    let dir = cli.dir.as_str();
+    let rev = cli.display_options.rev.as_deref();
+    let table = &cli.display_options.table;
+    // archives have no history, their files are reported without git information
+    let history = ArchiveKind::from_path(dir).is_none();
+    let mut total_commits = if history
+        && (cli.display_options.git || cli.display_options.all || table.needs_commits())
+    {
+        add_git_info(files, dir, rev, git_cache.as_deref_mut())
     } else {
         None
     };
+    if let (Some(SubmoduleMode::Recurse), Some(total)) = (cli.submodules, total_commits.as_mut()) {
+        *total += add_submodules_git_info(files, dir, &find_submodules(dir));
+    }
+
+    if history && table.needs_churn() {
+        add_churn_info(files, dir, rev, git_cache);
+    }
+
+    if history && (cli.display_options.age || table.needs_age()) {
+        add_age_info(files, dir, rev);
+    }
+
+    if cli.display_options.todos && total_commits.is_some() {
+        add_todo_blame(files, dir, rev);
+    }
+
+    if let Some(path) = &cli.save_baseline {
+        save_baseline(path, files, dir).unwrap();
+    }
+    let baseline = cli.baseline.as_deref().map(|x| load_baseline(x).unwrap());
+
+    display_info(
+        files,
+        dir,
+        &cli.display_options,
+        total_commits,
+        cli.num,
+        author_options,
+        baseline.as_ref(),
+    );
 
-    display_info(&files, dir, cli.display_options, total_commits, cli.num);
+    if !submodules.is_empty() {
+        display_submodule_info(submodules, &cli.display_options.table);
+    }
+
+    if cli.display_options.untracked {
+        display_untracked_info(untracked, &cli.display_options.table);
+    }
+}
+
+// leaves the files of submodules out for --submodules exclude, and returns them for separate
+fn split_submodules_of(files: &mut Vec<File>, cli: &Cli) -> Vec<SubmoduleFiles> {
+    let dir = cli.dir.as_str();
+    match cli.submodules {
+        Some(SubmoduleMode::Exclude) => {
+            exclude_submodules(files, &find_submodules(dir));
+            Vec::new()
+        }
+        Some(SubmoduleMode::Separate) => split_submodules(files, dir, &find_submodules(dir)),
+        _ => Vec::new(),
+    }
+}
+
+// leaves the files outside of the git index out for --tracked-only and --untracked, and
+// returns them to be reported separately
+fn split_untracked(files: &mut Vec<File>, dir: &str, options: &DisplayOptions) -> Vec<File> {
+    if !options.tracked_only && !options.untracked {
+        return Vec::new();
+    }
+    let tracked = tracked_files(dir).unwrap();
+    let (tracked_files, untracked) = files.drain(..).partition(|x| tracked.contains(&x.path));
+    *files = tracked_files;
+    untracked
 }
@@ -0,0 +1,231 @@
+use tree_sitter::{Language, Node, Parser};
+
+use crate::{
+    counter::{LineKind, SourceStats},
+    file::FileType,
+};
+
+fn language(path: &str, file_type: FileType) -> Option<Language> {
+    let language = match file_type {
+        FileType::Rust => tree_sitter_rust::LANGUAGE,
+        FileType::Go => tree_sitter_go::LANGUAGE,
+        FileType::Python => tree_sitter_python::LANGUAGE,
+        FileType::JavaScript => tree_sitter_javascript::LANGUAGE,
+        // the TSX grammar would read `<T>x` casts of plain TypeScript as elements
+        FileType::TypeScript if path.ends_with(".tsx") => tree_sitter_typescript::LANGUAGE_TSX,
+        FileType::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT,
+        FileType::Java => tree_sitter_java::LANGUAGE,
+        FileType::C | FileType::CHeader => tree_sitter_c::LANGUAGE,
+        FileType::CPlusPlus | FileType::Arduino => tree_sitter_cpp::LANGUAGE,
+        _ => return None,
+    };
+    Some(language.into())
+}
+
+/// Counts lines and symbols with the tree-sitter grammar of the file type, `None` for file
+/// types without a grammar. Only the counting uses the grammars, complexity and the functions
+/// listed by `--symbols` are still found with pattern rules.
+pub fn count_source(path: &str, content: &str, file_type: FileType) -> Option<SourceStats> {
+    let mut parser = Parser::new();
+    parser.set_language(&language(path, file_type)?).ok()?;
+    let tree = parser.parse(content, None)?;
+
+    let lines = content.lines().collect::<Vec<_>>();
+    let mut kinds = lines
+        .iter()
+        .map(|x| {
+            if x.trim().is_empty() {
+                LineKind::Blank
+            } else {
+                LineKind::Comment
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut has_comment = vec![false; lines.len()];
+    let mut stats = SourceStats::default();
+
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        let kind = node.kind();
+        if kind.contains("comment") {
+            for row in rows(&node, lines.len()) {
+                has_comment[row] = true;
+            }
+            continue;
+        }
+
+        let is_string = kind.contains("string") || kind == "template_literal";
+        if node.child_count() == 0 || is_string {
+            for row in rows(&node, lines.len()) {
+                if kinds[row] != LineKind::Blank {
+                    kinds[row] = LineKind::Code;
+                }
+            }
+            if is_string {
+                continue;
+            }
+        }
+
+        match symbol(&node, file_type) {
+            Some(Symbol::Function) => stats.functions += 1,
+            Some(Symbol::Class) => stats.classes += 1,
+            Some(Symbol::Import) => stats.imports += 1,
+            None => {}
+        }
+
+        let mut cursor = node.walk();
+        stack.extend(node.children(&mut cursor));
+    }
+
+    // lines that are neither blank nor code can only be comments if the parser saw one,
+    // tokens the grammar hides from the tree are counted as code
+    for (kind, has_comment) in kinds.iter_mut().zip(has_comment) {
+        if *kind == LineKind::Comment && !has_comment {
+            *kind = LineKind::Code;
+        }
+    }
+    LineKind::count(&kinds, &mut stats);
+
+    Some(stats)
+}
+
+// rows covered by the node, leaving out the row after a trailing newline
+fn rows(node: &Node, line_count: usize) -> std::ops::Range<usize> {
+    let start = node.start_position();
+    let end = node.end_position();
+    let last = if end.column == 0 && end.row > start.row {
+        end.row - 1
+    } else {
+        end.row
+    };
+    start.row.min(line_count)..(last + 1).min(line_count)
+}
+
+enum Symbol {
+    Function,
+    Class,
+    Import,
+}
+
+fn symbol(node: &Node, file_type: FileType) -> Option<Symbol> {
+    let has_body = node.child_by_field_name("body").is_some();
+    let symbol = match (file_type, node.kind()) {
+        (FileType::Rust, "function_item")
+        | (FileType::Go, "function_declaration" | "method_declaration")
+        | (FileType::Python, "function_definition")
+        | (
+            FileType::JavaScript | FileType::TypeScript,
+            "function_declaration" | "generator_function_declaration" | "method_definition",
+        )
+        | (FileType::Java, "constructor_declaration")
+        | (
+            FileType::C | FileType::CHeader | FileType::CPlusPlus | FileType::Arduino,
+            "function_definition",
+        ) => Symbol::Function,
+        (FileType::Java, "method_declaration") if has_body => Symbol::Function,
+        // only arrow functions and function expressions with a block that are assigned to a
+        // name, callbacks are part of the surrounding function
+        (FileType::JavaScript | FileType::TypeScript, "arrow_function" | "function_expression")
+            if node
+                .parent()
+                .is_some_and(|x| x.kind() == "variable_declarator")
+                && node
+                    .child_by_field_name("body")
+                    .is_some_and(|x| x.kind() == "statement_block") =>
+        {
+            Symbol::Function
+        }
+
+        (FileType::Rust, "struct_item" | "enum_item" | "union_item")
+        | (FileType::Python, "class_definition")
+        | (
+            FileType::JavaScript | FileType::TypeScript,
+            "class_declaration" | "abstract_class_declaration",
+        )
+        | (FileType::Java, "class_declaration" | "enum_declaration" | "record_declaration") => {
+            Symbol::Class
+        }
+        (FileType::Go, "type_spec")
+            if node
+                .child_by_field_name("type")
+                .is_some_and(|x| x.kind() == "struct_type") =>
+        {
+            Symbol::Class
+        }
+        (
+            FileType::C | FileType::CHeader | FileType::CPlusPlus | FileType::Arduino,
+            "struct_specifier" | "class_specifier" | "union_specifier",
+        ) if has_body => Symbol::Class,
+
+        (FileType::Rust, "use_declaration")
+        | (FileType::Go, "import_spec")
+        | (
+            FileType::Python,
+            "import_statement" | "import_from_statement" | "future_import_statement",
+        )
+        | (FileType::JavaScript | FileType::TypeScript, "import_statement")
+        | (FileType::Java, "import_declaration")
+        | (
+            FileType::C | FileType::CHeader | FileType::CPlusPlus | FileType::Arduino,
+            "preproc_include",
+        ) => Symbol::Import,
+        _ => return None,
+    };
+
+    Some(symbol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_source() {
+        let content = r#"use std::fs;
+
+/// A parsed file
+pub struct Parsed {
+    content: String, // the content
+}
+
+fn parse(path: &str) -> Parsed {
+    let content = "/* not a comment
+        // still a string */";
+    Parsed { content: fs::read_to_string(path).unwrap() }
+}
+"#;
+        assert_eq!(
+            count_source("src/parse.rs", content, FileType::Rust),
+            Some(SourceStats {
+                code: 9,
+                comments: 1,
+                blanks: 2,
+                functions: 1,
+                classes: 1,
+                imports: 1,
+            })
+        );
+
+        let content = "import { a } from './a';\n\n// helpers\nexport const load = async () => {\n  return [1].map((x) => x * 2);\n};\nclass A {\n  run() {}\n}\n";
+        assert_eq!(
+            count_source("src/load.ts", content, FileType::TypeScript),
+            Some(SourceStats {
+                code: 7,
+                comments: 1,
+                blanks: 1,
+                functions: 2,
+                classes: 1,
+                imports: 1,
+            })
+        );
+
+        // the TSX grammar reads the cast as an element and the comment as its text
+        let content = "const a = <number>value;\n// the total\nconst b = a + 1;\n";
+        let stats = count_source("src/a.ts", content, FileType::TypeScript).unwrap();
+        assert_eq!((stats.code, stats.comments), (2, 1));
+        let stats = count_source("src/a.tsx", content, FileType::TypeScript).unwrap();
+        assert_eq!((stats.code, stats.comments), (3, 0));
+
+        assert!(count_source("README.md", "# Title", FileType::Markdown).is_none());
+    }
+}
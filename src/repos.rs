@@ -0,0 +1,336 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use term_table::{
+    row::Row,
+    table_cell::{Alignment, TableCell},
+    TableBuilder, TableStyle,
+};
+
+use crate::{
+    archive::{get_archive_files, ArchiveKind},
+    cache::Cache,
+    cli::Cli,
+    config::load_config,
+    display::percentage,
+    file::{build_overrides, get_files, AnalysisOptions, File, FileType},
+    git::{
+        add_git_info, get_git_authors, is_git_repository, AuthorGrouping, AuthorOptions, GitAuthor,
+    },
+};
+
+/// The analysis of one repository of a combined summary.
+#[derive(Debug)]
+pub struct RepoReport {
+    pub dir: String,
+    pub files: Vec<File>,
+    /// `None` for directories outside of git and archives
+    pub total_commits: Option<usize>,
+    pub authors: Vec<GitAuthor>,
+}
+
+/// An author with the commits of all repositories, grouped like in a single repository.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MergedAuthor {
+    pub name: String,
+    pub commits: u32,
+    pub repos: usize,
+}
+
+/// The directories listed in a manifest, one per line. Blank lines and lines starting with `#`
+/// are skipped, relative paths are relative to the manifest.
+pub fn load_manifest(path: &str) -> Result<Vec<String>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("could not read manifest {path}"))?;
+    let base = Path::new(path).parent().unwrap_or(Path::new(""));
+
+    Ok(content
+        .lines()
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty() && !x.starts_with('#'))
+        .map(|x| base.join(x).to_string_lossy().to_string())
+        .collect())
+}
+
+/// Analyzes every repository on its own and prints the combined summary.
+pub fn run_repos(dirs: &[String], cli: &Cli, author_options: &AuthorOptions) -> Result<()> {
+    let reports = dirs
+        .iter()
+        .map(|dir| analyze_repo(dir, cli, author_options.grouping))
+        .collect::<Result<Vec<_>>>()?;
+    display_repos(&reports, cli.num, author_options);
+    Ok(())
+}
+
+fn analyze_repo(dir: &str, cli: &Cli, grouping: AuthorGrouping) -> Result<RepoReport> {
+    if !Path::new(dir).exists() {
+        bail!("could not find repository {dir}");
+    }
+    let overrides = build_overrides(dir, &cli.exclude, &cli.matches)?;
+    let skip_lockfiles = &cli.display_options.skip_lockfiles;
+    // the same options as a single run without flags for details, which shares the cache
+    let analysis = AnalysisOptions::default();
+
+    if let Some(kind) = ArchiveKind::from_path(dir) {
+        let files = get_archive_files(dir, kind, &overrides, skip_lockfiles, &analysis)?;
+        return Ok(RepoReport {
+            dir: dir.to_string(),
+            files,
+            total_commits: None,
+            authors: Vec::new(),
+        });
+    }
+
+    // every repository has its own aliases and excluded authors, the grouping is shared so the
+    // authors can be merged
+    let config = load_config(cli.config.as_deref(), dir)?;
+    let author_options = AuthorOptions::new(
+        &config.authors,
+        Some(grouping),
+        &cli.exclude_authors,
+        cli.skip_bots,
+    )?;
+
+    let mut cache = (!cli.no_cache).then(|| Cache::load(dir, &analysis));
+    let mut files = get_files(dir, overrides, skip_lockfiles, &analysis, cache.as_ref());
+    let mut total_commits = None;
+    let mut authors = Vec::new();
+    if let Some(cache) = cache.as_mut() {
+        cache.update_files(dir, &files);
+    }
+    if is_git_repository(dir) {
+        total_commits = add_git_info(&mut files, dir, None, cache.as_mut().map(|x| &mut x.git));
+        authors = get_git_authors(dir, None, usize::MAX, &author_options).unwrap_or_default();
+    }
+    if let Some(cache) = &cache {
+        cache.save();
+    }
+
+    Ok(RepoReport {
+        dir: dir.to_string(),
+        files,
+        total_commits,
+        authors,
+    })
+}
+
+/// Sums up the commits of authors with the same name, email or domain across repositories,
+/// most commits first.
+pub fn merge_authors(reports: &[RepoReport]) -> Vec<MergedAuthor> {
+    let mut merged: HashMap<&str, (u32, usize)> = HashMap::new();
+    for author in reports.iter().flat_map(|x| x.authors.iter()) {
+        let entry = merged.entry(author.name.as_str()).or_default();
+        entry.0 += author.commits;
+        entry.1 += 1;
+    }
+
+    let mut authors = merged
+        .into_iter()
+        .map(|(name, (commits, repos))| MergedAuthor {
+            name: name.to_string(),
+            commits,
+            repos,
+        })
+        .collect::<Vec<_>>();
+    authors.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.name.cmp(&b.name)));
+    authors
+}
+
+fn display_repos(reports: &[RepoReport], num: usize, author_options: &AuthorOptions) {
+    let total_files = reports.iter().map(|x| x.files.len()).sum::<usize>();
+    let total_loc = reports
+        .iter()
+        .flat_map(|x| x.files.iter())
+        .map(|x| x.loc)
+        .sum::<usize>();
+    println!(
+        "\n{} {}\n",
+        "Summary for repositories:".bright_blue().bold(),
+        reports.len()
+    );
+    println!(
+        "{} {}\n",
+        "Total number of files:".bright_blue(),
+        total_files
+    );
+    println!("{} {}\n", "Total lines of code:".bright_blue(), total_loc);
+
+    println!("\n===================================\n");
+    println!("{}\n", "Repositories".bright_green().bold());
+    let mut sorted_reports = reports.iter().collect::<Vec<_>>();
+    sorted_reports.sort_by_key(|x| Reverse(x.files.iter().map(|x| x.loc).sum::<usize>()));
+    let mut repo_table = TableBuilder::new()
+        .has_top_boarder(true)
+        .style(TableStyle::thin())
+        .build();
+    repo_table.add_row(header_row(
+        &[
+            "Repository",
+            "Number of files",
+            "Lines of Code",
+            "Main language",
+            "Number of commits",
+        ],
+        |x| x.bright_green().bold(),
+    ));
+    for report in sorted_reports {
+        let mut languages: HashMap<FileType, usize> = HashMap::new();
+        for file in report.files.iter() {
+            *languages.entry(file.file_type).or_default() += file.loc;
+        }
+        let main_language = languages
+            .into_iter()
+            .max_by_key(|(file_type, loc)| (*loc, Reverse(*file_type)))
+            .map(|(file_type, _)| file_type.to_string())
+            .unwrap_or_default();
+        repo_table.add_row(Row::new(vec![
+            report.dir.clone(),
+            report.files.len().to_string(),
+            report
+                .files
+                .iter()
+                .map(|x| x.loc)
+                .sum::<usize>()
+                .to_string(),
+            main_language,
+            report
+                .total_commits
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| String::from("-")),
+        ]));
+    }
+    println!("{}", repo_table.render());
+
+    println!("\n===================================\n");
+    println!(
+        "{}\n",
+        "Languages across all repositories".bright_purple().bold()
+    );
+    let mut languages: HashMap<FileType, (HashSet<&str>, usize, usize)> = HashMap::new();
+    for report in reports.iter() {
+        for file in report.files.iter() {
+            let entry = languages.entry(file.file_type).or_default();
+            entry.0.insert(report.dir.as_str());
+            entry.1 += 1;
+            entry.2 += file.loc;
+        }
+    }
+    let mut languages = languages.into_iter().collect::<Vec<_>>();
+    languages.sort_by_key(|(file_type, (_, _, loc))| (Reverse(*loc), *file_type));
+    let mut language_table = TableBuilder::new()
+        .has_top_boarder(true)
+        .style(TableStyle::thin())
+        .build();
+    language_table.add_row(header_row(
+        &[
+            "File type",
+            "Repositories",
+            "Number of files",
+            "Lines of Code",
+            "Share",
+        ],
+        |x| x.bright_red().bold(),
+    ));
+    for (file_type, (repos, files, loc)) in languages {
+        language_table.add_row(Row::new(vec![
+            file_type.to_string(),
+            repos.len().to_string(),
+            files.to_string(),
+            loc.to_string(),
+            format!("{:.1}%", percentage(loc, total_loc)),
+        ]));
+    }
+    println!("{}", language_table.render());
+
+    let authors = merge_authors(reports);
+    if authors.is_empty() {
+        return;
+    }
+    println!("\n===================================\n");
+    println!(
+        "{}\n",
+        "Most prolific contributors across all repositories"
+            .yellow()
+            .bold()
+    );
+    let mut author_table = TableBuilder::new()
+        .has_top_boarder(true)
+        .style(TableStyle::thin())
+        .build();
+    let author_header = match author_options.grouping {
+        AuthorGrouping::Name => "Author",
+        AuthorGrouping::Email => "Author email",
+        AuthorGrouping::Domain => "Email domain",
+    };
+    author_table.add_row(header_row(
+        &[author_header, "Number of commits", "Repositories"],
+        |x| x.yellow().bold(),
+    ));
+    for author in authors.iter().take(num) {
+        author_table.add_row(Row::new(vec![
+            author.name.clone(),
+            author.commits.to_string(),
+            author.repos.to_string(),
+        ]));
+    }
+    println!("{}", author_table.render());
+}
+
+fn header_row(labels: &[&str], style: impl Fn(&str) -> colored::ColoredString) -> Row<'static> {
+    Row::new(
+        labels
+            .iter()
+            .map(|x| TableCell::new_with_alignment(style(x), 1, Alignment::Center)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_authors() {
+        let author = |name: &str, commits: u32| GitAuthor {
+            name: name.to_string(),
+            commits,
+        };
+        let report = |dir: &str, authors: Vec<GitAuthor>| RepoReport {
+            dir: dir.to_string(),
+            files: Vec::new(),
+            total_commits: None,
+            authors,
+        };
+        let reports = [
+            report("api", vec![author("Alice", 12), author("Bob", 3)]),
+            report("web", vec![author("Bob", 10), author("Carol", 4)]),
+            report("docs.tgz", Vec::new()),
+        ];
+
+        assert_eq!(
+            merge_authors(&reports),
+            [
+                MergedAuthor {
+                    name: String::from("Bob"),
+                    commits: 13,
+                    repos: 2,
+                },
+                MergedAuthor {
+                    name: String::from("Alice"),
+                    commits: 12,
+                    repos: 1,
+                },
+                MergedAuthor {
+                    name: String::from("Carol"),
+                    commits: 4,
+                    repos: 1,
+                },
+            ]
+        );
+    }
+}
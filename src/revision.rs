@@ -0,0 +1,227 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    path::Path,
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use ignore::overrides::Override;
+
+use crate::file::{analyze_content, file_from_content, AnalysisOptions, File, FileType};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TreeEntry {
+    pub oid: String,
+    pub path: String,
+}
+
+/// Reads blobs straight from the object database through a long running `git cat-file --batch`.
+pub struct ObjectReader {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    files: HashMap<(String, String), Option<File>>,
+}
+
+impl ObjectReader {
+    pub fn new(dir: &str) -> Result<Self> {
+        let mut child = Command::new("git")
+            .args(["cat-file", "--batch"])
+            .current_dir(dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .context("failed to execute git cat-file")?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+
+        Ok(ObjectReader {
+            child,
+            stdin,
+            stdout,
+            files: HashMap::new(),
+        })
+    }
+
+    pub fn read_blob(&mut self, oid: &str) -> Result<Vec<u8>> {
+        writeln!(self.stdin, "{oid}")?;
+        self.stdin.flush()?;
+
+        let mut header = String::new();
+        self.stdout.read_line(&mut header)?;
+        let size = match header.split_whitespace().collect::<Vec<_>>()[..] {
+            [_, "blob", size] => size.parse::<usize>()?,
+            _ => bail!("could not read blob {oid}: {}", header.trim()),
+        };
+
+        // the content is followed by a single newline
+        let mut content = vec![0; size + 1];
+        self.stdout.read_exact(&mut content)?;
+        content.truncate(size);
+
+        Ok(content)
+    }
+
+    /// Builds the `File` for a blob, files that are not valid UTF-8 are skipped like in a directory walk.
+    /// Results are cached, as most blobs stay the same between revisions.
+    pub fn read_file(&mut self, entry: &TreeEntry) -> Result<Option<File>> {
+        let key = (entry.oid.clone(), entry.path.clone());
+        if let Some(file) = self.files.get(&key) {
+            return Ok(file.clone());
+        }
+
+        let content = self.read_blob(&entry.oid)?;
+        let file = String::from_utf8(content)
+            .ok()
+            .map(|content| file_from_content(&entry.path, &content));
+        self.files.insert(key, file.clone());
+
+        Ok(file)
+    }
+}
+
+impl Drop for ObjectReader {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Lists all blobs of a revision, with paths relative to `dir` like a directory walk.
+pub fn list_tree(dir: &str, rev: &str) -> Result<Vec<TreeEntry>> {
+    let output = Command::new("git")
+        .args(["ls-tree", "-r", "-z", rev])
+        .current_dir(dir)
+        .output()
+        .context("failed to execute git ls-tree")?;
+
+    if !output.status.success() {
+        bail!(
+            "could not read the tree of {rev}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    parse_tree_entries(&String::from_utf8_lossy(&output.stdout))
+}
+
+pub fn get_files_at_revision(
+    reader: &mut ObjectReader,
+    dir: &str,
+    rev: &str,
+    overrides: &Override,
+    skip_lockfiles: &bool,
+) -> Result<Vec<File>> {
+    let blobs = get_blobs_at_revision(reader, dir, rev, overrides, skip_lockfiles)?;
+
+    Ok(blobs.into_iter().map(|(_, file)| file).collect())
+}
+
+/// The files of a revision with the same analyses as a directory walk, used by `--rev`.
+pub fn get_analyzed_files_at_revision(
+    dir: &str,
+    rev: &str,
+    overrides: &Override,
+    skip_lockfiles: &bool,
+    analysis: &AnalysisOptions,
+) -> Result<Vec<File>> {
+    let mut reader = ObjectReader::new(dir)?;
+    let mut files: Vec<File> = Vec::new();
+
+    for entry in list_tree(dir, rev)? {
+        if is_overridden(overrides, &entry.path) {
+            continue;
+        }
+        let Ok(content) = String::from_utf8(reader.read_blob(&entry.oid)?) else {
+            continue;
+        };
+        let file = analyze_content(&entry.path, &content, analysis);
+        if *skip_lockfiles && file.file_type == FileType::Lockfile {
+            continue;
+        }
+        files.push(file)
+    }
+
+    Ok(files)
+}
+
+/// Like `get_files_at_revision`, but keeps the object id of every file to tell changed files apart.
+pub fn get_blobs_at_revision(
+    reader: &mut ObjectReader,
+    dir: &str,
+    rev: &str,
+    overrides: &Override,
+    skip_lockfiles: &bool,
+) -> Result<Vec<(String, File)>> {
+    let mut blobs: Vec<(String, File)> = Vec::new();
+
+    for entry in list_tree(dir, rev)? {
+        if is_overridden(overrides, &entry.path) {
+            continue;
+        }
+        if let Some(file) = reader.read_file(&entry)? {
+            if *skip_lockfiles && file.file_type == FileType::Lockfile {
+                continue;
+            }
+            blobs.push((entry.oid, file))
+        }
+    }
+
+    Ok(blobs)
+}
+
+// there is no walk that prunes excluded directories, so the parent directories are checked as well
+pub fn is_overridden(overrides: &Override, path: &str) -> bool {
+    overrides.matched(path, false).is_ignore()
+        || Path::new(path)
+            .ancestors()
+            .skip(1)
+            .filter(|x| !x.as_os_str().is_empty())
+            .any(|x| overrides.matched(x, true).is_ignore())
+}
+
+fn parse_tree_entries(input: &str) -> Result<Vec<TreeEntry>> {
+    let mut entries = Vec::new();
+
+    for line in input.split('\0').filter(|x| !x.is_empty()) {
+        let (info, path) = line
+            .split_once('\t')
+            .ok_or_else(|| anyhow!("invalid ls-tree entry {line}"))?;
+        // submodules show up as commits, symlinks have mode 120000
+        if let [mode, "blob", oid] = info.split(' ').collect::<Vec<_>>()[..] {
+            if mode != "120000" {
+                entries.push(TreeEntry {
+                    oid: oid.to_string(),
+                    path: path.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tree_entries() {
+        let test_str = "100644 blob 3b18e51\tsrc/main.rs\x00120000 blob 9fa2c1b\tlink.rs\x00160000 commit 8d1c9ee\tvendor/lib\x00100755 blob a1b2c3d\tscripts/run me.sh\x00";
+
+        assert_eq!(
+            parse_tree_entries(test_str).unwrap(),
+            vec![
+                TreeEntry {
+                    oid: String::from("3b18e51"),
+                    path: String::from("src/main.rs"),
+                },
+                TreeEntry {
+                    oid: String::from("a1b2c3d"),
+                    path: String::from("scripts/run me.sh"),
+                },
+            ]
+        );
+    }
+}
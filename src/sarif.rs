@@ -0,0 +1,110 @@
+use clap::crate_version;
+use serde_json::{json, Value};
+
+use crate::check::{RuleId, Violation};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/DerTimonius/code-peek";
+
+/// Builds a SARIF 2.1.0 log with one result per violation.
+/// Violations about a whole group of files point to the rules file, if there is one.
+pub fn sarif_report(violations: &[Violation], rules_path: Option<&str>) -> Value {
+    let rules = RuleId::ALL
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.id(),
+                "shortDescription": { "text": rule.description() },
+                "helpUri": INFORMATION_URI,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let results = violations
+        .iter()
+        .map(|violation| {
+            let mut result = json!({
+                "ruleId": violation.rule.id(),
+                "ruleIndex": RuleId::ALL.iter().position(|x| *x == violation.rule),
+                "level": violation.level.id(),
+                "message": { "text": violation.message },
+            });
+            if let Some(path) = violation.path.as_deref().or(rules_path) {
+                result["locations"] = json!([{
+                    "physicalLocation": {
+                        "artifactLocation": {
+                            "uri": path.replace('\\', "/"),
+                            "uriBaseId": "%SRCROOT%",
+                        },
+                        "region": { "startLine": 1 },
+                    }
+                }]);
+            }
+            result
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "code-peek",
+                    "version": crate_version!(),
+                    "informationUri": INFORMATION_URI,
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::Level;
+
+    #[test]
+    fn test_sarif_report() {
+        let violations = vec![
+            Violation {
+                rule: RuleId::MaxLocPerFile,
+                level: Level::Error,
+                path: Some(String::from("src/main.rs")),
+                message: String::from("src/main.rs has 1200 lines of code"),
+            },
+            Violation {
+                rule: RuleId::MaxGrowth,
+                level: Level::Warning,
+                path: None,
+                message: String::from("Python files grew by 6.0%"),
+            },
+        ];
+
+        let report = sarif_report(&violations, Some(".code-peek.toml"));
+        let run = &report["runs"][0];
+
+        assert_eq!(report["version"], "2.1.0");
+        assert_eq!(
+            run["tool"]["driver"]["rules"].as_array().unwrap().len(),
+            RuleId::ALL.len()
+        );
+        assert_eq!(run["results"][0]["ruleId"], "max-loc-per-file");
+        assert_eq!(run["results"][0]["ruleIndex"], 0);
+        assert_eq!(run["results"][0]["level"], "error");
+        assert_eq!(
+            run["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "src/main.rs"
+        );
+        assert_eq!(run["results"][1]["level"], "warning");
+        assert_eq!(
+            run["results"][1]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            ".code-peek.toml"
+        );
+
+        let report = sarif_report(&violations[1..], None);
+        assert!(report["runs"][0]["results"][0].get("locations").is_none());
+    }
+}
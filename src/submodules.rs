@@ -0,0 +1,207 @@
+use std::{fs, path::Path, process::Command};
+
+use crate::{
+    file::File,
+    git::{add_git_info, is_git_repository},
+};
+
+/// How the files of submodules are analyzed, without a mode they are walked like any other
+/// directory and their history is ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmoduleMode {
+    /// Leave the files of submodules out
+    Exclude,
+    /// Report every submodule in its own section, with its own history
+    Separate,
+    /// Count the files of submodules in the summary, with the history of the submodule
+    Recurse,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Submodule {
+    pub name: String,
+    /// Path relative to the analyzed directory
+    pub path: String,
+}
+
+/// The files of a submodule with paths relative to the submodule, see [`split_submodules`].
+#[derive(Debug)]
+pub struct SubmoduleFiles {
+    pub submodule: Submodule,
+    pub files: Vec<File>,
+    /// `None` if the submodule is not checked out
+    pub total_commits: Option<usize>,
+}
+
+/// Submodules declared in the `.gitmodules` of the repository of `dir` that are inside `dir`.
+pub fn find_submodules(dir: &str) -> Vec<Submodule> {
+    let Ok(output) = Command::new("git")
+        .args(["rev-parse", "--show-toplevel", "--show-prefix"])
+        .current_dir(dir)
+        .output()
+    else {
+        return Vec::new();
+    };
+    if !output.status.success() {
+        return Vec::new();
+    }
+    let output_str = String::from_utf8_lossy(&output.stdout);
+    let mut lines = output_str.lines();
+    let (Some(root), prefix) = (lines.next(), lines.next().unwrap_or_default()) else {
+        return Vec::new();
+    };
+    let Ok(content) = fs::read_to_string(Path::new(root).join(".gitmodules")) else {
+        return Vec::new();
+    };
+
+    // `.gitmodules` paths are relative to the repository root, `dir` may be below it
+    parse_gitmodules(&content)
+        .into_iter()
+        .filter_map(|x| {
+            let path = x.path.strip_prefix(prefix)?.to_string();
+            Some(Submodule { path, ..x })
+        })
+        .collect()
+}
+
+fn parse_gitmodules(content: &str) -> Vec<Submodule> {
+    let mut submodules = Vec::new();
+    let mut name = None;
+
+    for line in content.lines().map(|x| x.trim()) {
+        if let Some(section) = line.strip_prefix("[submodule \"") {
+            name = section.strip_suffix("\"]").map(String::from);
+            continue;
+        }
+        if line.starts_with('[') {
+            name = None;
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if let (Some(name), "path") = (&name, key.trim()) {
+            submodules.push(Submodule {
+                name: name.clone(),
+                path: value.trim().trim_end_matches('/').to_string(),
+            });
+        }
+    }
+
+    submodules
+}
+
+fn is_below(file: &File, submodule: &Submodule) -> bool {
+    Path::new(&file.path).starts_with(&submodule.path)
+}
+
+/// Leaves the files of submodules out.
+pub fn exclude_submodules(files: &mut Vec<File>, submodules: &[Submodule]) {
+    files.retain(|file| !submodules.iter().any(|x| is_below(file, x)));
+}
+
+/// Moves the files of every submodule out of `files`, with the commits from the history of
+/// the submodule.
+pub fn split_submodules(
+    files: &mut Vec<File>,
+    dir: &str,
+    submodules: &[Submodule],
+) -> Vec<SubmoduleFiles> {
+    submodules
+        .iter()
+        .map(|submodule| {
+            let (mut below, rest) = files.drain(..).partition(|x| is_below(x, submodule));
+            *files = rest;
+            let total_commits = add_submodule_git_info(&mut below, dir, submodule);
+            let files = below
+                .into_iter()
+                .map(|mut x| {
+                    x.path = relative_path(&x.path, submodule);
+                    x
+                })
+                .collect();
+            SubmoduleFiles {
+                submodule: submodule.clone(),
+                files,
+                total_commits,
+            }
+        })
+        .collect()
+}
+
+/// Replaces the commits of the files of every submodule with the commits from the history of
+/// the submodule, and returns the sum of the submodule commits.
+pub fn add_submodules_git_info(files: &mut [File], dir: &str, submodules: &[Submodule]) -> usize {
+    let mut total_commits = 0;
+    for submodule in submodules {
+        let mut below = files
+            .iter_mut()
+            .filter(|x| is_below(x, submodule))
+            .collect::<Vec<_>>();
+        let mut counted = below.iter().map(|x| (*x).clone()).collect::<Vec<_>>();
+        if let Some(commits) = add_submodule_git_info(&mut counted, dir, submodule) {
+            total_commits += commits;
+            for (file, counted) in below.iter_mut().zip(counted) {
+                file.commits = counted.commits;
+            }
+        }
+    }
+    total_commits
+}
+
+// runs `add_git_info` in the root of the submodule, the files need paths relative to it
+fn add_submodule_git_info(files: &mut [File], dir: &str, submodule: &Submodule) -> Option<usize> {
+    let root = Path::new(dir).join(&submodule.path);
+    let root = root.to_str()?;
+    if !root_is_repository(root) {
+        return None;
+    }
+    let mut relative = files
+        .iter()
+        .map(|x| File {
+            path: relative_path(&x.path, submodule),
+            ..x.clone()
+        })
+        .collect::<Vec<_>>();
+    let total_commits = add_git_info(&mut relative, root, None, None)?;
+    for (file, counted) in files.iter_mut().zip(relative) {
+        file.commits = counted.commits;
+    }
+    Some(total_commits)
+}
+
+// a submodule that is not checked out is an empty directory of the outer repository
+fn root_is_repository(root: &str) -> bool {
+    Path::new(root).join(".git").exists() && is_git_repository(root)
+}
+
+fn relative_path(path: &str, submodule: &Submodule) -> String {
+    Path::new(path)
+        .strip_prefix(&submodule.path)
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gitmodules() {
+        let test_str = "[submodule \"lib\"]\n\tpath = vendor/lib\n\turl = https://example.com/lib.git\n[core]\n\tpath = ignored\n[submodule \"docs\"]\n\turl = ../docs.git\n\tpath = docs/\n";
+
+        assert_eq!(
+            parse_gitmodules(test_str),
+            vec![
+                Submodule {
+                    name: String::from("lib"),
+                    path: String::from("vendor/lib"),
+                },
+                Submodule {
+                    name: String::from("docs"),
+                    path: String::from("docs"),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    complexity::{functions, FunctionInfo},
+    file::FileType,
+    syntax::{strip_source, syntax, SourceLine},
+};
+
+/// Functions and declared types of a source file, found with pattern rules per language.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Symbols {
+    pub functions: Vec<FunctionInfo>,
+    /// Structs, classes, enums and unions
+    pub types: usize,
+    /// Traits, interfaces and protocols
+    pub traits: usize,
+    pub tests: usize,
+}
+
+struct SymbolPatterns {
+    types: Regex,
+    traits: Option<Regex>,
+    tests: Regex,
+}
+
+fn symbol_patterns(file_type: FileType) -> Option<SymbolPatterns> {
+    let (types, traits, tests) = match file_type {
+        FileType::Rust => (
+            r"^\s*(pub(\([^)]*\))?\s+)?(struct|enum|union)\s+\w+",
+            Some(r"^\s*(pub(\([^)]*\))?\s+)?(unsafe\s+)?trait\s+\w+"),
+            r"^\s*#\[(\w+::)*test\]",
+        ),
+        FileType::Go => (
+            r"^\s*(type\s+)?\w+(\[[^\]]*\])?\s+struct\s*\{",
+            Some(r"^\s*(type\s+)?\w+(\[[^\]]*\])?\s+interface\s*\{"),
+            r"^\s*func\s+(Test|Benchmark|Fuzz|Example)\w*\s*\(",
+        ),
+        FileType::Python => (
+            r"^\s*class\s+\w+",
+            Some(r"^\s*class\s+\w+\s*\([^)]*\b(ABC|Protocol)\b"),
+            r"^\s*(async\s+)?def\s+test\w*\s*\(",
+        ),
+        FileType::Mojo => (
+            r"^\s*(class|struct)\s+\w+",
+            Some(r"^\s*trait\s+\w+"),
+            r"^\s*(async\s+)?(def|fn)\s+test\w*\s*\(",
+        ),
+        FileType::JavaScript => (
+            r"^\s*(export\s+)?(default\s+)?class\s+\w+",
+            None,
+            r"^\s*(it|test)(\.\w+)?\s*\(",
+        ),
+        FileType::TypeScript => (
+            r"^\s*(export\s+)?(default\s+)?(abstract\s+)?class\s+\w+",
+            Some(r"^\s*(export\s+)?(default\s+)?interface\s+\w+"),
+            r"^\s*(it|test)(\.\w+)?\s*\(",
+        ),
+        FileType::Java => (
+            r"^\s*((public|private|protected|static|final|abstract|sealed)\s+)*(class|enum|record)\s+\w+",
+            Some(r"^\s*((public|private|protected|static|sealed)\s+)*interface\s+\w+"),
+            r"^\s*@(Test|ParameterizedTest)\b",
+        ),
+        FileType::CSharp => (
+            r"^\s*((public|private|protected|internal|static|sealed|abstract|partial)\s+)*(class|struct|record|enum)\s+\w+",
+            Some(r"^\s*((public|private|protected|internal|partial)\s+)*interface\s+\w+"),
+            r"^\s*\[(Test|Fact|Theory|TestMethod)\b",
+        ),
+        FileType::C | FileType::CHeader | FileType::CPlusPlus | FileType::Arduino => (
+            r"^\s*(typedef\s+)?(struct|class|union)\s+\w+[^;]*$",
+            None,
+            r"^\s*(TEST|TEST_F|TEST_P|TEST_CASE)\s*\(",
+        ),
+        _ => return None,
+    };
+
+    Some(SymbolPatterns {
+        types: Regex::new(types).unwrap(),
+        traits: traits.map(|x| Regex::new(x).unwrap()),
+        tests: Regex::new(tests).unwrap(),
+    })
+}
+
+/// Symbol summary of a source file, `None` for file types without pattern rules.
+pub fn symbols(content: &str, file_type: FileType) -> Option<Symbols> {
+    let syntax = syntax(file_type)?;
+    find_symbols(&strip_source(content, &syntax), file_type)
+}
+
+/// Symbol summary of lines stripped by [`strip_source`].
+pub fn find_symbols(lines: &[SourceLine], file_type: FileType) -> Option<Symbols> {
+    let patterns = symbol_patterns(file_type)?;
+    let count = |regex: &Regex| lines.iter().filter(|x| regex.is_match(&x.code)).count();
+
+    Some(Symbols {
+        functions: functions(lines, file_type),
+        types: count(&patterns.types),
+        traits: patterns.traits.as_ref().map(count).unwrap_or(0),
+        tests: count(&patterns.tests),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbols() {
+        let content = r#"pub trait Shape {
+    fn area(&self) -> f64;
+}
+
+struct Square(f64);
+
+impl Shape for Square {
+    fn area(&self) -> f64 {
+        self.0 * self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_area() {
+        assert_eq!(Square(2.0).area(), 4.0);
+    }
+
+    #[tokio::test]
+    async fn test_async() {}
+}
+"#;
+        let summary = symbols(content, FileType::Rust).unwrap();
+        assert_eq!(
+            summary
+                .functions
+                .iter()
+                .map(|x| (x.name.as_str(), x.line, x.length))
+                .collect::<Vec<_>>(),
+            vec![("area", 8, 3), ("test_area", 16, 3), ("test_async", 21, 1)]
+        );
+        assert_eq!((summary.types, summary.traits, summary.tests), (1, 1, 2));
+
+        let content = "export interface Props {\n  name: string;\n}\nexport class Store {}\ndescribe('store', () => {\n  it('works', () => {});\n  test.each([1])('x', () => {});\n});\n";
+        let summary = symbols(content, FileType::TypeScript).unwrap();
+        assert_eq!((summary.types, summary.traits, summary.tests), (1, 1, 2));
+
+        assert!(symbols("# Title", FileType::Markdown).is_none());
+    }
+}
@@ -0,0 +1,359 @@
+use crate::file::FileType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quote {
+    pub open: &'static str,
+    pub close: &'static str,
+    pub escapes: bool,
+    pub multiline: bool,
+}
+
+const fn quote(open: &'static str, close: &'static str, escapes: bool, multiline: bool) -> Quote {
+    Quote {
+        open,
+        close,
+        escapes,
+        multiline,
+    }
+}
+
+/// Comment and string delimiters of a language, used to strip comments and string contents
+/// before looking for keywords and braces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Syntax {
+    pub line_comments: &'static [&'static str],
+    pub block_comment: Option<(&'static str, &'static str)>,
+    /// Checked in order, so longer delimiters have to come first
+    pub quotes: &'static [Quote],
+    /// Rust style char literals, where a single quote can also start a lifetime
+    pub char_literals: bool,
+}
+
+const C_STYLE_QUOTES: [Quote; 3] = [
+    quote("\"\"\"", "\"\"\"", true, true),
+    quote("\"", "\"", true, false),
+    quote("'", "'", true, false),
+];
+
+const RUST_QUOTES: [Quote; 3] = [
+    quote("r#\"", "\"#", false, true),
+    quote("r\"", "\"", false, true),
+    quote("\"", "\"", true, true),
+];
+
+const GO_QUOTES: [Quote; 3] = [
+    quote("`", "`", false, true),
+    quote("\"", "\"", true, false),
+    quote("'", "'", true, false),
+];
+
+const JS_QUOTES: [Quote; 3] = [
+    quote("`", "`", true, true),
+    quote("\"", "\"", true, false),
+    quote("'", "'", true, false),
+];
+
+const PYTHON_QUOTES: [Quote; 4] = [
+    quote("\"\"\"", "\"\"\"", true, true),
+    quote("'''", "'''", true, true),
+    quote("\"", "\"", true, false),
+    quote("'", "'", true, false),
+];
+
+pub fn syntax(file_type: FileType) -> Option<Syntax> {
+    match file_type {
+        FileType::Rust => Some(Syntax {
+            line_comments: &["//"],
+            block_comment: Some(("/*", "*/")),
+            quotes: &RUST_QUOTES,
+            char_literals: true,
+        }),
+        FileType::Go => Some(Syntax {
+            line_comments: &["//"],
+            block_comment: Some(("/*", "*/")),
+            quotes: &GO_QUOTES,
+            char_literals: false,
+        }),
+        FileType::JavaScript | FileType::TypeScript => Some(Syntax {
+            line_comments: &["//"],
+            block_comment: Some(("/*", "*/")),
+            quotes: &JS_QUOTES,
+            char_literals: false,
+        }),
+        FileType::Java
+        | FileType::C
+        | FileType::CHeader
+        | FileType::CPlusPlus
+        | FileType::CSharp
+        | FileType::Arduino => Some(Syntax {
+            line_comments: &["//"],
+            block_comment: Some(("/*", "*/")),
+            quotes: &C_STYLE_QUOTES,
+            char_literals: false,
+        }),
+        FileType::Python | FileType::Mojo => Some(Syntax {
+            line_comments: &["#"],
+            block_comment: None,
+            quotes: &PYTHON_QUOTES,
+            char_literals: false,
+        }),
+        _ => None,
+    }
+}
+
+const HASH_COMMENTS: Syntax = comments_only(&["#"], None, &STRING_QUOTES);
+const DASH_COMMENTS: Syntax = comments_only(&["--"], None, &STRING_QUOTES);
+const SLASH_COMMENTS: Syntax = comments_only(&["//"], Some(("/*", "*/")), &STRING_QUOTES);
+const MARKUP_COMMENTS: Syntax = comments_only(&["//"], Some(("<!--", "-->")), &STRING_QUOTES);
+const CSS_COMMENTS: Syntax = comments_only(&[], Some(("/*", "*/")), &STRING_QUOTES);
+// a double quote starts a comment in vim script
+const VIM_COMMENTS: Syntax = comments_only(&["\""], None, &[]);
+
+const STRING_QUOTES: [Quote; 1] = [quote("\"", "\"", true, false)];
+
+const fn comments_only(
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [Quote],
+) -> Syntax {
+    Syntax {
+        line_comments,
+        block_comment,
+        quotes,
+        char_literals: false,
+    }
+}
+
+/// Syntax of the source languages, or just the comments of other file types that have them.
+pub fn comment_syntax(file_type: FileType) -> Option<Syntax> {
+    if let Some(syntax) = syntax(file_type) {
+        return Some(syntax);
+    }
+
+    match file_type {
+        FileType::Docker
+        | FileType::Elixir
+        | FileType::GraphQL
+        | FileType::Julia
+        | FileType::TOML
+        | FileType::YAML => Some(HASH_COMMENTS),
+        FileType::Lua | FileType::SQL => Some(DASH_COMMENTS),
+        FileType::Gleam | FileType::Prisma | FileType::Swift | FileType::Zig => {
+            Some(SLASH_COMMENTS)
+        }
+        FileType::Astro | FileType::HTML | FileType::SVG | FileType::Svelte | FileType::Vue => {
+            Some(MARKUP_COMMENTS)
+        }
+        FileType::CSS => Some(CSS_COMMENTS),
+        FileType::VimScript => Some(VIM_COMMENTS),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLine {
+    /// The line without comments, string contents are removed but the quotes are kept
+    pub code: String,
+    pub has_comment: bool,
+    /// Text of the comments on the line, without the delimiters
+    pub comment: String,
+}
+
+enum State {
+    Code,
+    BlockComment,
+    String(Quote),
+}
+
+pub fn strip_source(content: &str, syntax: &Syntax) -> Vec<SourceLine> {
+    scan(content, syntax, false)
+}
+
+/// The lines without comments, keeping strings as they are.
+pub fn strip_comments(content: &str, syntax: &Syntax) -> Vec<String> {
+    scan(content, syntax, true)
+        .into_iter()
+        .map(|x| x.code)
+        .collect()
+}
+
+fn scan(content: &str, syntax: &Syntax, keep_strings: bool) -> Vec<SourceLine> {
+    let mut state = State::Code;
+    let mut lines = Vec::new();
+
+    for line in content.lines() {
+        let mut code = String::new();
+        let mut comment = String::new();
+        let mut has_comment = matches!(state, State::BlockComment);
+        let mut rest = line;
+
+        while !rest.is_empty() {
+            match state {
+                State::BlockComment => {
+                    let (_, close) = syntax.block_comment.unwrap();
+                    match rest.find(close) {
+                        Some(index) => {
+                            comment.push_str(&rest[..index]);
+                            rest = &rest[index + close.len()..];
+                            state = State::Code;
+                        }
+                        None => {
+                            comment.push_str(rest);
+                            rest = "";
+                        }
+                    }
+                }
+                State::String(quote) => {
+                    let mut chars = rest.char_indices();
+                    let mut end = None;
+                    while let Some((index, ch)) = chars.next() {
+                        if quote.escapes && ch == '\\' {
+                            chars.next();
+                        } else if rest[index..].starts_with(quote.close) {
+                            end = Some(index);
+                            break;
+                        }
+                    }
+                    let end_of_string = end.unwrap_or(rest.len());
+                    if keep_strings {
+                        code.push_str(&rest[..end_of_string]);
+                    }
+                    match end {
+                        Some(index) => {
+                            code.push_str(quote.close);
+                            rest = &rest[index + quote.close.len()..];
+                            state = State::Code;
+                        }
+                        None => rest = "",
+                    }
+                }
+                State::Code => {
+                    if let Some(open) = syntax.line_comments.iter().find(|x| rest.starts_with(*x)) {
+                        has_comment = true;
+                        comment.push_str(&rest[open.len()..]);
+                        rest = "";
+                    } else if let Some((open, _)) = syntax
+                        .block_comment
+                        .filter(|(open, _)| rest.starts_with(open))
+                    {
+                        has_comment = true;
+                        rest = &rest[open.len()..];
+                        state = State::BlockComment;
+                    } else if let Some(quote) = syntax
+                        .quotes
+                        .iter()
+                        .find(|x| rest.starts_with(x.open) && !is_identifier_end(&code, x.open))
+                    {
+                        code.push_str(quote.open);
+                        rest = &rest[quote.open.len()..];
+                        state = State::String(*quote);
+                    } else if let Some(length) = char_literal_length(rest, syntax) {
+                        code.push_str(if keep_strings { &rest[..length] } else { "''" });
+                        rest = &rest[length..];
+                    } else {
+                        let ch = rest.chars().next().unwrap();
+                        code.push(ch);
+                        rest = &rest[ch.len_utf8()..];
+                    }
+                }
+            }
+        }
+
+        if let State::String(quote) = state {
+            if !quote.multiline {
+                state = State::Code;
+            }
+        }
+
+        lines.push(SourceLine {
+            code,
+            has_comment,
+            comment,
+        });
+    }
+
+    lines
+}
+
+// raw string prefixes like r" must not match the end of an identifier like `for"`
+fn is_identifier_end(code: &str, open: &str) -> bool {
+    open.starts_with(|x: char| x.is_alphabetic())
+        && code
+            .chars()
+            .last()
+            .is_some_and(|x| x.is_alphanumeric() || x == '_')
+}
+
+// 'a' and '\n' are char literals, while 'a without a closing quote is a lifetime
+fn char_literal_length(rest: &str, syntax: &Syntax) -> Option<usize> {
+    if !syntax.char_literals || !rest.starts_with('\'') {
+        return None;
+    }
+
+    let mut chars = rest.char_indices().skip(1);
+    match chars.next() {
+        Some((_, '\\')) => rest.get(3..)?.find('\'').map(|x| x + 4),
+        Some(_) => match chars.next() {
+            Some((index, '\'')) => Some(index + 1),
+            _ => None,
+        },
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(content: &str, file_type: FileType) -> Vec<String> {
+        strip_source(content, &syntax(file_type).unwrap())
+            .into_iter()
+            .map(|x| x.code)
+            .collect()
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let content = "let a = 1; // if x\n/* if y\n  while z */ let b = 2;\n";
+        let lines = strip_source(content, &syntax(FileType::Rust).unwrap());
+
+        assert_eq!(lines[0].code, "let a = 1; ");
+        assert!(lines[0].has_comment);
+        assert_eq!(lines[0].comment, " if x");
+        assert_eq!(lines[1].comment, " if y");
+        assert_eq!(lines[2].comment, "  while z ");
+        assert_eq!(lines[1].code, "");
+        assert_eq!(lines[2].code, " let b = 2;");
+        assert!(lines[2].has_comment);
+    }
+
+    #[test]
+    fn test_strip_strings() {
+        assert_eq!(
+            code(r#"let url = "http://x.y/*"; if a {"#, FileType::Rust),
+            vec![r#"let url = ""; if a {"#]
+        );
+        assert_eq!(
+            code(
+                "fn a<'a>(x: &'a str) -> char { '{' }\nlet s = r#\"}\"#;",
+                FileType::Rust
+            ),
+            vec!["fn a<'a>(x: &'a str) -> char { '' }", "let s = r#\"\"#;"]
+        );
+        assert_eq!(
+            code("x = \"\"\"\nif not # a\n\"\"\" # if\nfor", FileType::Python),
+            vec!["x = \"\"\"", "", "\"\"\" ", "for"]
+        );
+        assert_eq!(
+            code("const s = 'it\\'s // {';", FileType::TypeScript),
+            vec!["const s = '';"]
+        );
+        assert_eq!(
+            strip_comments(
+                "let c = '\\''; // quote\nlet s = \"/* a */\"; /* b */",
+                &syntax(FileType::Rust).unwrap()
+            ),
+            vec!["let c = '\\''; ", "let s = \"/* a */\"; "]
+        );
+    }
+}
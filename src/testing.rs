@@ -0,0 +1,237 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+
+use crate::{
+    file::{File, FileType},
+    syntax::{strip_source, syntax},
+};
+
+const TEST_DIRECTORIES: [&str; 3] = ["tests", "test", "__tests__"];
+
+/// Whether the whole file is test code, based on the naming conventions of its file type.
+pub fn is_test_file(path: &str, file_type: FileType) -> bool {
+    let path = Path::new(path);
+    let in_test_directory = path.parent().is_some_and(|x| {
+        x.iter()
+            .any(|x| TEST_DIRECTORIES.iter().any(|dir| x == *dir))
+    });
+    if in_test_directory {
+        return true;
+    }
+
+    let name = path
+        .file_name()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let stem = name.split('.').next().unwrap_or_default();
+    match file_type {
+        FileType::Go => name.ends_with("_test.go"),
+        FileType::JavaScript | FileType::TypeScript | FileType::Svelte => {
+            name.contains(".spec.") || name.contains(".test.")
+        }
+        FileType::Python => {
+            stem.starts_with("test_") || stem.ends_with("_test") || stem == "conftest"
+        }
+        FileType::Java | FileType::CSharp => stem.ends_with("Test") || stem.ends_with("Tests"),
+        _ => false,
+    }
+}
+
+/// Lines of test code in a file: all of them for test files, the items behind
+/// `#[cfg(test)]` for Rust and none otherwise.
+pub fn test_loc(path: &str, content: &str, file_type: FileType, loc: usize) -> usize {
+    if is_test_file(path, file_type) {
+        return loc;
+    }
+    match file_type {
+        FileType::Rust => rust_tests(content).0,
+        _ => 0,
+    }
+}
+
+/// Paths of the files a Rust file declares as test modules with `#[cfg(test)] mod tests;`,
+/// both `tests.rs` and `tests/mod.rs` as either can hold the module.
+pub fn test_modules(path: &str, content: &str, file_type: FileType) -> Vec<String> {
+    if file_type != FileType::Rust {
+        return Vec::new();
+    }
+    let file = Path::new(path);
+    let parent = file.parent().unwrap_or(Path::new(""));
+    let stem = file
+        .file_stem()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+    // modules of main.rs, lib.rs and mod.rs are next to them, others in a directory of their name
+    let module_dir = match stem.as_str() {
+        "main" | "lib" | "mod" => parent.to_path_buf(),
+        _ => parent.join(stem),
+    };
+
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for module in rust_tests(content).1 {
+        match module.path {
+            Some(module_path) => paths.push(parent.join(module_path)),
+            None => {
+                paths.push(module_dir.join(format!("{}.rs", module.name)));
+                paths.push(module_dir.join(&module.name).join("mod.rs"));
+            }
+        }
+    }
+    paths
+        .into_iter()
+        .map(|x| x.to_string_lossy().to_string())
+        .collect()
+}
+
+/// Files that are test code as a whole because another file declares them as a test module.
+pub fn test_module_files(files: &[File]) -> HashSet<&str> {
+    files
+        .iter()
+        .flat_map(|x| x.test_modules.iter())
+        .map(|x| x.as_str())
+        .collect()
+}
+
+// a module behind `#[cfg(test)]` whose items are in another file
+struct TestModule {
+    name: String,
+    /// Path of a `#[path = "..."]` attribute
+    path: Option<String>,
+}
+
+// counts the lines from a `#[cfg(test)]` attribute to the end of the item it annotates, and
+// collects the modules declared without a body
+fn rust_tests(content: &str) -> (usize, Vec<TestModule>) {
+    let syntax = syntax(FileType::Rust).unwrap();
+    let module_pattern =
+        Regex::new(r"^(#\[[^\]]*\]\s*)*(pub(\([^)]*\))?\s+)?mod\s+(?<name>\w+)\s*;$").unwrap();
+    let path_pattern = Regex::new(r#"#\[path\s*=\s*"(?<path>[^"]+)"\]"#).unwrap();
+    let mut test_loc = 0;
+    let mut modules = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut depth = 0;
+    let mut item = String::new();
+    let mut module_path = None;
+
+    let lines = strip_source(content, &syntax);
+    for (index, (line, raw)) in lines.iter().zip(content.lines()).enumerate() {
+        let mut code = line.code.trim();
+        if start.is_none() {
+            // the item can also follow the attribute on the same line
+            let Some(rest) = code.strip_prefix("#[cfg(test)]") else {
+                continue;
+            };
+            start = Some(index);
+            depth = 0;
+            item.clear();
+            module_path = None;
+            code = rest;
+        }
+        // string contents are stripped from the code, the path is read from the raw line
+        if let Some(captures) = path_pattern.captures(raw) {
+            module_path = Some(captures["path"].to_string());
+        }
+        item.push_str(code);
+        item.push(' ');
+
+        if item_end(code, &mut depth) {
+            test_loc += index - start.unwrap() + 1;
+            start = None;
+            if let Some(captures) = module_pattern.captures(item.trim()) {
+                modules.push(TestModule {
+                    name: captures["name"].to_string(),
+                    path: module_path.take(),
+                });
+            }
+        }
+    }
+
+    (test_loc, modules)
+}
+
+// whether the braces of the item are balanced again, or it ended with a semicolon
+fn item_end(code: &str, depth: &mut i32) -> bool {
+    let mut opened = *depth > 0;
+    for ch in code.chars() {
+        match ch {
+            '{' => {
+                *depth += 1;
+                opened = true;
+            }
+            '}' => {
+                *depth -= 1;
+                if opened && *depth == 0 {
+                    return true;
+                }
+            }
+            ';' if !opened => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_test_file() {
+        assert!(is_test_file("pkg/server_test.go", FileType::Go));
+        assert!(!is_test_file("pkg/server.go", FileType::Go));
+        assert!(is_test_file("src/app.spec.ts", FileType::TypeScript));
+        assert!(is_test_file("src/app.test.jsx", FileType::JavaScript));
+        assert!(is_test_file("test_app.py", FileType::Python));
+        assert!(!is_test_file("testing.py", FileType::Python));
+        assert!(is_test_file("tests/cli.rs", FileType::Rust));
+        assert!(is_test_file("src/__tests__/App.tsx", FileType::TypeScript));
+        assert!(!is_test_file("src/tests.rs", FileType::Rust));
+    }
+
+    #[test]
+    fn test_rust_test_loc() {
+        let content = r#"fn main() {}
+
+#[cfg(test)]
+use std::fs;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_main() {
+        let braces = "}}}";
+        main();
+    }
+}
+"#;
+        assert_eq!(rust_tests(content).0, 12);
+        assert_eq!(test_loc("src/main.rs", content, FileType::Rust, 16), 12);
+        assert_eq!(test_loc("tests/main.rs", content, FileType::Rust, 16), 16);
+    }
+
+    #[test]
+    fn test_test_modules() {
+        let content = "mod parser;\n\n#[cfg(test)]\nmod tests;\n\n#[cfg(test)]\n#[path = \"fixtures.rs\"]\nmod fixtures;\n";
+        let path = |x: &str| Path::new(x).to_string_lossy().to_string();
+
+        assert_eq!(
+            test_modules("src/lib.rs", content, FileType::Rust),
+            [
+                path("src/tests.rs"),
+                path("src/tests/mod.rs"),
+                path("src/fixtures.rs")
+            ]
+        );
+        assert_eq!(
+            test_modules("src/parser.rs", "#[cfg(test)] mod tests;", FileType::Rust)[0],
+            Path::new("src/parser/tests.rs").to_string_lossy()
+        );
+        assert!(test_modules("src/app.py", content, FileType::Python).is_empty());
+    }
+}
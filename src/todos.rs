@@ -0,0 +1,136 @@
+use std::cmp::Reverse;
+
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    file::FileType,
+    syntax::{comment_syntax, strip_source},
+};
+
+pub const DEFAULT_MARKERS: [&str; 4] = ["TODO", "FIXME", "HACK", "XXX"];
+
+// longer marker texts are cut off, the line number leads to the rest
+const MAX_TEXT_LENGTH: usize = 80;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct TodoMarker {
+    pub marker: String,
+    /// 1-based line of the comment
+    pub line: usize,
+    /// Comment text following the marker
+    pub text: String,
+    /// Author and unix timestamp of the line, from git blame
+    pub blame: Option<(String, u64)>,
+}
+
+/// Regex matching any of the default and custom markers at the start of a word, see
+/// [`find_todos`] for the end of it.
+pub fn marker_regex(custom_markers: &[String]) -> Result<Regex> {
+    let mut markers = DEFAULT_MARKERS
+        .iter()
+        .map(|x| x.to_string())
+        .chain(custom_markers.iter().map(|x| x.trim().to_string()))
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>();
+    // the longest of markers starting alike has to win
+    markers.sort_by_key(|x| Reverse(x.len()));
+    let markers = markers.iter().map(|x| regex::escape(x)).collect::<Vec<_>>();
+
+    // `\b` needs a word character next to it, which custom markers like `@todo` or `!!!` lack
+    RegexBuilder::new(&format!(r"(?:^|\W)({})", markers.join("|")))
+        .build()
+        .context("invalid todo markers")
+}
+
+/// Markers in the comments of a file, `None` for file types without known comment syntax.
+pub fn find_todos(content: &str, file_type: FileType, markers: &Regex) -> Option<Vec<TodoMarker>> {
+    let syntax = comment_syntax(file_type)?;
+
+    let todos = strip_source(content, &syntax)
+        .into_iter()
+        .enumerate()
+        .flat_map(|(index, line)| {
+            markers
+                .captures_iter(&line.comment)
+                .filter_map(|captures| {
+                    let marker = captures.get(1).unwrap();
+                    let rest = &line.comment[marker.end()..];
+                    // the regex crate has no lookahead, so the end of the word is checked here
+                    if rest.starts_with(|x: char| x.is_alphanumeric() || x == '_') {
+                        return None;
+                    }
+                    let text = rest
+                        .strip_prefix(':')
+                        .unwrap_or(rest)
+                        .trim()
+                        .chars()
+                        .take(MAX_TEXT_LENGTH)
+                        .collect();
+                    Some(TodoMarker {
+                        marker: marker.as_str().to_string(),
+                        line: index + 1,
+                        text,
+                        blame: None,
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    Some(todos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_todos() {
+        let markers = marker_regex(&[String::from("PERF")]).unwrap();
+        let content = r#"// TODO: split this up
+fn main() {
+    let todo = "TODO not a comment";
+    /* FIXME(timon) handle errors
+       and also XXX this */
+    run(); // PERF: cache it
+    // TODOS and NOTE are no markers
+}
+"#;
+        let todos = find_todos(content, FileType::Rust, &markers).unwrap();
+        assert_eq!(
+            todos
+                .iter()
+                .map(|x| (x.marker.as_str(), x.line, x.text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("TODO", 1, "split this up"),
+                ("FIXME", 4, "(timon) handle errors"),
+                ("XXX", 5, "this"),
+                ("PERF", 6, "cache it"),
+            ]
+        );
+
+        let todos = find_todos("key: 1 # HACK: remove\n", FileType::YAML, &markers).unwrap();
+        assert_eq!(todos[0].text, "remove");
+        assert!(find_todos("TODO", FileType::Markdown, &markers).is_none());
+
+        let markers = marker_regex(&[String::from("@todo"), String::from("!!!")]).unwrap();
+        let todos = find_todos(
+            "# @todo: rename
+# !!! TODOS x@todo @todox
+",
+            FileType::Python,
+            &markers,
+        )
+        .unwrap();
+        assert_eq!(
+            todos
+                .iter()
+                .map(|x| (x.marker.as_str(), x.text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("@todo", "rename"), ("!!!", "TODOS x@todo @todox")]
+        );
+    }
+}
@@ -0,0 +1,687 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Cell, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::{
+    columns::{Column, SortKey, SortOrder, TableOptions},
+    display::{column_cells, column_headers, percentage},
+    file::{File, FileType},
+    git::GitAuthor,
+};
+
+// rows moved by page up and page down
+const PAGE: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Languages,
+    Files,
+    Contributors,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileView {
+    List,
+    Tree,
+}
+
+/// Entry of the directory tree, a subdirectory with all files below it or a single file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TreeEntry {
+    name: String,
+    is_dir: bool,
+    files: usize,
+    loc: usize,
+}
+
+struct App {
+    files: Vec<File>,
+    authors: Vec<GitAuthor>,
+    dir: String,
+    total_commits: Option<usize>,
+    /// File types with their number of files and lines of code, largest first
+    languages: Vec<(FileType, usize, usize)>,
+    focus: Pane,
+    view: FileView,
+    language: Option<FileType>,
+    filter: String,
+    editing_filter: bool,
+    table: TableOptions,
+    /// Components of the directory shown by the tree view
+    directory: Vec<String>,
+    language_state: TableState,
+    file_state: TableState,
+    author_state: TableState,
+    quit: bool,
+}
+
+/// Opens a full screen view of the files and git authors until `q` is pressed. The file list
+/// starts with the sort key, order and columns of `table`.
+pub fn run_tui(
+    files: Vec<File>,
+    authors: Vec<GitAuthor>,
+    dir: &str,
+    total_commits: Option<usize>,
+    table: &TableOptions,
+) -> Result<()> {
+    let mut app = App::new(files, authors, dir, total_commits, table);
+    let mut terminal = ratatui::init();
+
+    let result = (|| -> Result<()> {
+        while !app.quit {
+            terminal.draw(|frame| app.render(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key);
+                }
+            }
+        }
+        Ok(())
+    })();
+
+    ratatui::restore();
+    result
+}
+
+impl App {
+    fn new(
+        files: Vec<File>,
+        authors: Vec<GitAuthor>,
+        dir: &str,
+        total_commits: Option<usize>,
+        table: &TableOptions,
+    ) -> Self {
+        let mut grouped: HashMap<FileType, (usize, usize)> = HashMap::new();
+        for file in files.iter() {
+            let entry = grouped.entry(file.file_type).or_default();
+            entry.0 += 1;
+            entry.1 += file.loc;
+        }
+        let mut languages = grouped
+            .into_iter()
+            .map(|(file_type, (files, loc))| (file_type, files, loc))
+            .collect::<Vec<_>>();
+        languages.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+
+        let first = |len: usize| TableState::default().with_selected((len > 0).then_some(0));
+        App {
+            language_state: first(languages.len()),
+            file_state: first(files.len()),
+            author_state: first(authors.len()),
+            files,
+            authors,
+            dir: dir.to_string(),
+            total_commits,
+            languages,
+            focus: Pane::Files,
+            view: FileView::List,
+            language: None,
+            filter: String::new(),
+            editing_filter: false,
+            table: table.clone(),
+            directory: Vec::new(),
+            quit: false,
+        }
+    }
+
+    // files of the selected language matching the filter, in the chosen order
+    fn visible_files(&self) -> Vec<&File> {
+        let filter = self.filter.to_lowercase();
+        let mut files = self
+            .files
+            .iter()
+            .filter(|x| self.language.is_none_or(|language| x.file_type == language))
+            .filter(|x| x.path.to_lowercase().contains(&filter))
+            .collect::<Vec<_>>();
+        self.table.sort(&mut files, SortKey::Loc);
+        files
+    }
+
+    fn tree_entries(&self) -> Vec<TreeEntry> {
+        directory_entries(&self.visible_files(), &self.directory, &self.table)
+    }
+
+    fn sort_keys(&self) -> Vec<SortKey> {
+        let mut keys = vec![SortKey::Loc, SortKey::Bytes];
+        if self.total_commits.is_some() {
+            keys.push(SortKey::Commits);
+        }
+        keys.extend([SortKey::Name, SortKey::Path]);
+        keys
+    }
+
+    // the commits only with git information
+    fn file_columns(&self) -> Vec<Column> {
+        let mut columns = vec![Column::Path, Column::Loc, Column::Bytes];
+        if self.total_commits.is_some() {
+            columns.push(Column::Commits);
+        }
+        self.table.columns(columns)
+    }
+
+    fn sort_key(&self) -> SortKey {
+        self.table.sort_by.unwrap_or(SortKey::Loc)
+    }
+
+    fn rows(&self, pane: Pane) -> usize {
+        match (pane, self.view) {
+            (Pane::Languages, _) => self.languages.len(),
+            (Pane::Contributors, _) => self.authors.len(),
+            (Pane::Files, FileView::List) => self.visible_files().len(),
+            (Pane::Files, FileView::Tree) => self.tree_entries().len(),
+        }
+    }
+
+    fn state(&mut self, pane: Pane) -> &mut TableState {
+        match pane {
+            Pane::Languages => &mut self.language_state,
+            Pane::Files => &mut self.file_state,
+            Pane::Contributors => &mut self.author_state,
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let rows = self.rows(self.focus);
+        let state = self.state(self.focus);
+        if rows == 0 {
+            state.select(None);
+            return;
+        }
+        let current = state.selected().unwrap_or(0) as isize;
+        state.select(Some((current + delta).clamp(0, rows as isize - 1) as usize));
+    }
+
+    // the file list changed, so the old selection points at another row
+    fn reset_files(&mut self) {
+        let rows = self.rows(Pane::Files);
+        self.file_state = TableState::default().with_selected((rows > 0).then_some(0));
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+
+        if self.editing_filter {
+            match key.code {
+                KeyCode::Enter => self.editing_filter = false,
+                KeyCode::Esc => {
+                    self.editing_filter = false;
+                    self.filter.clear();
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                }
+                KeyCode::Char(ch) => self.filter.push(ch),
+                _ => return,
+            }
+            self.reset_files();
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Esc if self.filter.is_empty() && self.language.is_none() => self.quit = true,
+            KeyCode::Esc => {
+                self.filter.clear();
+                self.language = None;
+                self.reset_files();
+            }
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Pane::Languages => Pane::Files,
+                    Pane::Files => Pane::Contributors,
+                    Pane::Contributors => Pane::Languages,
+                }
+            }
+            KeyCode::BackTab => {
+                self.focus = match self.focus {
+                    Pane::Languages => Pane::Contributors,
+                    Pane::Files => Pane::Languages,
+                    Pane::Contributors => Pane::Files,
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::PageDown => self.move_selection(PAGE as isize),
+            KeyCode::PageUp => self.move_selection(-(PAGE as isize)),
+            KeyCode::Home | KeyCode::Char('g') => self.move_selection(isize::MIN / 2),
+            KeyCode::End | KeyCode::Char('G') => self.move_selection(isize::MAX / 2),
+            KeyCode::Char('/') => {
+                self.editing_filter = true;
+                self.focus = Pane::Files;
+            }
+            KeyCode::Char('t') => {
+                self.view = match self.view {
+                    FileView::List => FileView::Tree,
+                    FileView::Tree => FileView::List,
+                };
+                self.reset_files();
+            }
+            KeyCode::Char('s') => {
+                let keys = self.sort_keys();
+                let index = keys.iter().position(|x| *x == self.sort_key()).unwrap_or(0);
+                self.table.sort_by = Some(keys[(index + 1) % keys.len()]);
+                self.table.order = None;
+                self.reset_files();
+            }
+            KeyCode::Char('r') => {
+                let order = match self.table.order {
+                    Some(SortOrder::Ascending) => SortOrder::Descending,
+                    Some(SortOrder::Descending) => SortOrder::Ascending,
+                    None => match self.sort_key().default_order() {
+                        SortOrder::Ascending => SortOrder::Descending,
+                        SortOrder::Descending => SortOrder::Ascending,
+                    },
+                };
+                self.table.order = Some(order);
+                self.reset_files();
+            }
+            KeyCode::Enter => self.select(),
+            KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h')
+                if self.focus == Pane::Files
+                    && self.view == FileView::Tree
+                    && !self.directory.is_empty() =>
+            {
+                self.directory.pop();
+                self.reset_files();
+            }
+            _ => {}
+        }
+    }
+
+    // filters by the selected language, or opens the selected directory of the tree
+    fn select(&mut self) {
+        match (self.focus, self.view) {
+            (Pane::Languages, _) => {
+                let Some(index) = self.language_state.selected() else {
+                    return;
+                };
+                let language = self.languages[index].0;
+                self.language = (self.language != Some(language)).then_some(language);
+                self.directory.clear();
+                self.reset_files();
+            }
+            (Pane::Files, FileView::Tree) => {
+                let entries = self.tree_entries();
+                let Some(entry) = self.file_state.selected().and_then(|x| entries.get(x)) else {
+                    return;
+                };
+                if entry.is_dir {
+                    self.directory.push(entry.name.clone());
+                    self.reset_files();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let [header, main, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)])
+                .areas(main);
+        let [languages, contributors] =
+            Layout::vertical([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(left);
+
+        let total_loc = self.files.iter().map(|x| x.loc).sum::<usize>();
+        let mut summary = vec![
+            Span::from(format!(" code-peek {} ", self.dir)).bold(),
+            Span::from(format!(
+                "· {} files · {} lines",
+                self.files.len(),
+                total_loc
+            )),
+        ];
+        if let Some(commits) = self.total_commits {
+            summary.push(Span::from(format!(" · {commits} commits")));
+        }
+        frame.render_widget(Line::from(summary).fg(Color::Cyan), header);
+
+        self.render_languages(frame, languages, total_loc);
+        self.render_contributors(frame, contributors);
+        match self.view {
+            FileView::List => self.render_files(frame, right),
+            FileView::Tree => self.render_tree(frame, right),
+        }
+
+        let help = if self.editing_filter {
+            format!(" filter: {}▏ (enter to apply, esc to clear)", self.filter)
+        } else {
+            " q quit · tab switch pane · ↑↓ move · enter select · / filter · s sort · r reverse · t tree/list · ⌫ up · esc clear".to_string()
+        };
+        frame.render_widget(Paragraph::new(help).dark_gray(), footer);
+    }
+
+    fn block(&self, pane: Pane, title: String) -> Block<'static> {
+        let style = match self.focus == pane {
+            true => Style::new().fg(Color::Cyan),
+            false => Style::new().fg(Color::DarkGray),
+        };
+        Block::bordered().title(title).border_style(style)
+    }
+
+    fn render_languages(&mut self, frame: &mut Frame, area: Rect, total_loc: usize) {
+        let rows = self.languages.iter().map(|(file_type, files, loc)| {
+            let style = match self.language == Some(*file_type) {
+                true => Style::new().fg(Color::Green),
+                false => Style::new(),
+            };
+            Row::new(vec![
+                file_type.to_string(),
+                files.to_string(),
+                loc.to_string(),
+                format!("{:.1}%", percentage(*loc, total_loc)),
+            ])
+            .style(style)
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(6),
+                Constraint::Length(8),
+                Constraint::Length(6),
+            ],
+        )
+        .header(header_row(["File type", "Files", "Lines", "Share"]))
+        .row_highlight_style(highlight())
+        .block(self.block(
+            Pane::Languages,
+            format!(" Languages ({}) ", self.languages.len()),
+        ));
+        frame.render_stateful_widget(table, area, &mut self.language_state);
+    }
+
+    fn render_contributors(&mut self, frame: &mut Frame, area: Rect) {
+        let block = self.block(Pane::Contributors, " Contributors ".to_string());
+        if self.authors.is_empty() {
+            frame.render_widget(Paragraph::new("No git history found").block(block), area);
+            return;
+        }
+
+        let total = self
+            .authors
+            .iter()
+            .map(|x| x.commits as usize)
+            .sum::<usize>();
+        let rows = self.authors.iter().map(|author| {
+            Row::new(vec![
+                author.name.clone(),
+                author.commits.to_string(),
+                format!("{:.1}%", percentage(author.commits as usize, total)),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(8),
+                Constraint::Length(6),
+            ],
+        )
+        .header(header_row(["Author", "Commits", "Share"]))
+        .row_highlight_style(highlight())
+        .block(block);
+        frame.render_stateful_widget(table, area, &mut self.author_state);
+    }
+
+    fn files_title(&self, view: &str) -> String {
+        let mut title = format!(" {view}");
+        if let Some(language) = self.language {
+            title += &format!(" · {language}");
+        }
+        if !self.filter.is_empty() {
+            title += &format!(" · \"{}\"", self.filter);
+        }
+        let arrow = match self.table.order.unwrap_or(self.sort_key().default_order()) {
+            SortOrder::Ascending => "↑",
+            SortOrder::Descending => "↓",
+        };
+        title += &format!(" · by {} {arrow} ", sort_label(self.sort_key()));
+        title
+    }
+
+    fn render_files(&mut self, frame: &mut Frame, area: Rect) {
+        let files = self.visible_files();
+        let columns = self.file_columns();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or(0);
+        let rows = files
+            .iter()
+            .map(|file| {
+                Row::new(
+                    columns
+                        .iter()
+                        .flat_map(|x| column_cells(file, *x, None, now))
+                        .map(Cell::from)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let header = columns
+            .iter()
+            .flat_map(|x| {
+                column_headers(*x, "File")
+                    .into_iter()
+                    .map(move |label| (*x, label))
+            })
+            .collect::<Vec<_>>();
+        let widths = header.iter().map(|(column, label)| match column {
+            Column::Path => Constraint::Fill(1),
+            _ => Constraint::Length(label.len().max(8) as u16),
+        });
+        let title = format!("{}({}) ", self.files_title("Files"), files.len());
+        let table = Table::new(rows, widths)
+            .header(header_row(header.iter().map(|(_, label)| *label)))
+            .row_highlight_style(highlight())
+            .block(self.block(Pane::Files, title));
+        frame.render_stateful_widget(table, area, &mut self.file_state);
+    }
+
+    fn render_tree(&mut self, frame: &mut Frame, area: Rect) {
+        let entries = self.tree_entries();
+        let rows = entries.iter().map(|entry| {
+            let (name, style) = match entry.is_dir {
+                true => (format!("{}/", entry.name), Style::new().fg(Color::Blue)),
+                false => (entry.name.clone(), Style::new()),
+            };
+            Row::new(vec![name, entry.files.to_string(), entry.loc.to_string()]).style(style)
+        });
+        let path = match self.directory.is_empty() {
+            true => ".".to_string(),
+            false => self.directory.join("/"),
+        };
+        let title = format!("{}{path} ", self.files_title("Tree"));
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(7),
+                Constraint::Length(8),
+            ],
+        )
+        .header(header_row(["Name", "Files", "Lines"]))
+        .row_highlight_style(highlight())
+        .block(self.block(Pane::Files, title));
+        frame.render_stateful_widget(table, area, &mut self.file_state);
+    }
+}
+
+/// Subdirectories and files directly inside `directory`, each directory with the sum of the
+/// files below it, sorted like the file list.
+fn directory_entries(
+    files: &[&File],
+    directory: &[String],
+    table: &TableOptions,
+) -> Vec<TreeEntry> {
+    let mut directories: HashMap<String, (usize, usize)> = HashMap::new();
+    let mut entries = Vec::new();
+    for file in files {
+        let components = Path::new(&file.path)
+            .iter()
+            .map(|x| x.to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        if components.len() <= directory.len() || components[..directory.len()] != *directory {
+            continue;
+        }
+
+        let name = components[directory.len()].clone();
+        if components.len() == directory.len() + 1 {
+            entries.push(TreeEntry {
+                name,
+                is_dir: false,
+                files: 1,
+                loc: file.loc,
+            });
+        } else {
+            let entry = directories.entry(name).or_default();
+            entry.0 += 1;
+            entry.1 += file.loc;
+        }
+    }
+    entries.extend(
+        directories
+            .into_iter()
+            .map(|(name, (files, loc))| TreeEntry {
+                name,
+                is_dir: true,
+                files,
+                loc,
+            }),
+    );
+
+    let key = table.sort_by.unwrap_or(SortKey::Loc);
+    let order = table.order.unwrap_or(key.default_order());
+    entries.sort_by(|a, b| {
+        // directories have no single value for the other keys, so they are sorted by lines
+        let ordering = match key {
+            SortKey::Name | SortKey::Path => a.name.cmp(&b.name),
+            _ => a.loc.cmp(&b.loc).then(b.name.cmp(&a.name)),
+        };
+        match order {
+            SortOrder::Ascending => ordering,
+            SortOrder::Descending => ordering.reverse(),
+        }
+    });
+    entries
+}
+
+fn header_row<'a>(labels: impl IntoIterator<Item = &'a str>) -> Row<'a> {
+    Row::new(labels).style(Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+}
+
+fn highlight() -> Style {
+    Style::new().add_modifier(Modifier::REVERSED)
+}
+
+fn sort_label(key: SortKey) -> &'static str {
+    match key {
+        SortKey::Loc => "lines",
+        SortKey::Commits => "commits",
+        SortKey::Churn => "churn",
+        SortKey::Bytes => "size",
+        SortKey::Name => "name",
+        SortKey::Path => "path",
+        SortKey::Age => "age",
+        SortKey::Todos => "todos",
+        SortKey::Functions => "functions",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::file_from_content;
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn test_app() {
+        let files = vec![
+            file_from_content("src/main.rs", "fn main() {}\n"),
+            file_from_content("src/cli/args.rs", "fn a() {}\nfn b() {}\nfn c() {}\n"),
+            file_from_content("README.md", "# Title\n\nText\n\nMore\n"),
+        ];
+        let mut app = App::new(files, Vec::new(), ".", None, &TableOptions::default());
+        let paths = |app: &App| {
+            app.visible_files()
+                .iter()
+                .map(|x| x.path.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(paths(&app), ["README.md", "src/cli/args.rs", "src/main.rs"]);
+
+        press(&mut app, KeyCode::Char('t'));
+        let entries = app.tree_entries();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|x| (x.name.as_str(), x.is_dir, x.files, x.loc))
+                .collect::<Vec<_>>(),
+            [("README.md", false, 1, 5), ("src", true, 2, 4)]
+        );
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.directory, ["src"]);
+        assert_eq!(app.tree_entries()[0].name, "cli");
+        press(&mut app, KeyCode::Backspace);
+        assert!(app.directory.is_empty());
+
+        // the languages are sorted by lines of code, markdown first
+        press(&mut app, KeyCode::BackTab);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.language, Some(FileType::Markdown));
+        assert_eq!(paths(&app), ["README.md"]);
+        press(&mut app, KeyCode::Esc);
+
+        press(&mut app, KeyCode::Char('/'));
+        for ch in "ARGS".chars() {
+            press(&mut app, KeyCode::Char(ch));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(paths(&app), ["src/cli/args.rs"]);
+
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.sort_key(), SortKey::Bytes);
+        press(&mut app, KeyCode::Char('r'));
+        assert_eq!(paths(&app)[0], "src/main.rs");
+
+        press(&mut app, KeyCode::Char('q'));
+        assert!(app.quit);
+
+        let table = TableOptions {
+            sort_by: Some(SortKey::Name),
+            columns: Some(vec![Column::Name, Column::Type]),
+            ..Default::default()
+        };
+        let app = App::new(app.files, Vec::new(), ".", None, &table);
+        assert_eq!(paths(&app), ["README.md", "src/cli/args.rs", "src/main.rs"]);
+        assert_eq!(app.file_columns(), [Column::Name, Column::Type]);
+    }
+}
@@ -0,0 +1,218 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use ignore::{overrides::Override, WalkBuilder};
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use crate::file::{get_files_below, AnalysisOptions, File};
+
+// editors and git write several files at once, they are collected into one update
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Renders the files, then keeps them up to date with the changes in `dir` and renders them
+/// again after every batch of changes, until the process is stopped.
+pub fn watch_files(
+    dir: &str,
+    overrides: &Override,
+    skip_lockfiles: &bool,
+    analysis: &AnalysisOptions,
+    mut files: Vec<File>,
+    mut render: impl FnMut(&mut Vec<File>),
+) -> Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).context("could not start watching")?;
+    watcher
+        .watch(Path::new(dir), RecursiveMode::Recursive)
+        .with_context(|| format!("could not watch {dir}"))?;
+    let root = fs::canonicalize(dir).with_context(|| format!("could not resolve {dir}"))?;
+
+    render_watched(dir, &mut files, &mut render);
+    loop {
+        let mut changed = BTreeSet::new();
+        let mut event = receiver.recv().context("file watcher stopped")?;
+        loop {
+            match event {
+                Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                    changed.extend(
+                        event
+                            .paths
+                            .iter()
+                            .filter_map(|x| relative_path(x, dir, &root)),
+                    );
+                }
+                Ok(_) => {}
+                Err(err) => eprintln!("ERROR: {err}"),
+            }
+            match receiver.recv_timeout(DEBOUNCE) {
+                Ok(next) => event = next,
+                Err(_) => break,
+            }
+        }
+
+        let changed = changed.into_iter().collect::<Vec<_>>();
+        if update_files(
+            &mut files,
+            dir,
+            &changed,
+            overrides,
+            skip_lockfiles,
+            analysis,
+        ) {
+            render_watched(dir, &mut files, &mut render);
+        }
+    }
+}
+
+fn render_watched(dir: &str, files: &mut Vec<File>, render: &mut impl FnMut(&mut Vec<File>)) {
+    // clears the terminal and moves the cursor to the top
+    print!("\x1B[2J\x1B[1;1H");
+    render(files);
+    println!("\nWatching {dir} for changes, press Ctrl+C to stop");
+}
+
+// the path relative to `dir`, `None` for paths outside of it and inside `.git`
+fn relative_path(path: &Path, dir: &str, root: &Path) -> Option<PathBuf> {
+    let relative = path
+        .strip_prefix(dir)
+        .or_else(|_| path.strip_prefix(root))
+        .ok()?;
+    if relative.as_os_str().is_empty() || relative.starts_with(".git") {
+        return None;
+    }
+    Some(relative.to_path_buf())
+}
+
+/// Replaces the files at or below the changed paths, relative to `dir`, with their current
+/// content. Returns whether any file was added, changed or removed.
+pub fn update_files(
+    files: &mut Vec<File>,
+    dir: &str,
+    changed: &[PathBuf],
+    overrides: &Override,
+    skip_lockfiles: &bool,
+    analysis: &AnalysisOptions,
+) -> bool {
+    let mut updated = false;
+    for relative in changed {
+        let before = files.len();
+        files.retain(|x| !Path::new(&x.path).starts_with(relative));
+        updated |= files.len() != before;
+
+        let path = Path::new(dir).join(relative);
+        if path.exists() && is_walked(dir, relative, overrides) {
+            let added = get_files_below(
+                dir,
+                &path,
+                overrides.clone(),
+                skip_lockfiles,
+                analysis,
+                None,
+            );
+            updated |= !added.is_empty();
+            files.extend(added);
+        }
+    }
+    updated
+}
+
+// whether the walk of `get_files` would visit the path relative to `dir`, which it only does
+// if neither the path nor any directory between it and `dir` is ignored or excluded
+fn is_walked(dir: &str, relative: &Path, overrides: &Override) -> bool {
+    relative
+        .ancestors()
+        .filter(|x| !x.as_os_str().is_empty())
+        .all(|ancestor| {
+            let path = Path::new(dir).join(ancestor);
+            let Some(parent) = path.parent() else {
+                return true;
+            };
+            // the walk applies the ignore files of the parent directories and the overrides
+            WalkBuilder::new(parent)
+                .overrides(overrides.clone())
+                .hidden(false)
+                .max_depth(Some(1))
+                .build()
+                .filter_map(|x| x.ok())
+                .any(|x| x.depth() == 1 && x.path() == path)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{file::get_files, temp_dir::TempDir};
+    use ignore::overrides::OverrideBuilder;
+
+    #[test]
+    fn test_update_files() {
+        let dir = TempDir::new("watch");
+        fs::create_dir(dir.join(".git")).unwrap();
+        dir.write(".gitignore", "build/\n");
+        dir.write("src/main.rs", "fn main() {}\n");
+        dir.write("notes.txt", "skipped\n");
+        let dir_str = dir.to_str();
+
+        let mut builder = OverrideBuilder::new(dir_str);
+        builder.add("!*.txt").unwrap();
+        builder.add("!vendor/").unwrap();
+        let overrides = builder.build().unwrap();
+        let analysis = AnalysisOptions::default();
+        let mut files = get_files(dir_str, overrides.clone(), &false, &analysis, None);
+        assert_eq!(files.len(), 2);
+
+        dir.write("src/main.rs", "fn main() {\n    run();\n}\n");
+        dir.write("src/cli/args.rs", "struct Args;\n");
+        dir.write("todo.txt", "also skipped\n");
+        let changed = ["src/main.rs", "src/cli", "todo.txt"].map(PathBuf::from);
+        assert!(update_files(
+            &mut files, dir_str, &changed, &overrides, &false, &analysis
+        ));
+        let mut paths = files
+            .iter()
+            .map(|x| (x.path.as_str(), x.loc))
+            .collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                (".gitignore", 1),
+                ("src/cli/args.rs", 1),
+                ("src/main.rs", 3)
+            ]
+        );
+
+        fs::remove_dir_all(dir.join("src/cli")).unwrap();
+        assert!(update_files(
+            &mut files,
+            dir_str,
+            &[PathBuf::from("src/cli")],
+            &overrides,
+            &false,
+            &analysis
+        ));
+        assert_eq!(files.len(), 2);
+        assert!(!update_files(
+            &mut files,
+            dir_str,
+            &[PathBuf::from("notes.txt")],
+            &overrides,
+            &false,
+            &analysis
+        ));
+
+        // new files below ignored and excluded directories stay out
+        dir.write("build/out/gen.rs", "fn generated() {}\n");
+        dir.write("vendor/lib/dep.rs", "fn dep() {}\n");
+        let changed = ["build/out/gen.rs", "vendor/lib"].map(PathBuf::from);
+        assert!(!update_files(
+            &mut files, dir_str, &changed, &overrides, &false, &analysis
+        ));
+        assert_eq!(files.len(), 2);
+    }
+}